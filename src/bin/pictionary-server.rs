@@ -0,0 +1,37 @@
+use std::{env, process};
+use pictionary::config::{Config, Role};
+use pictionary::server::Server;
+
+fn main() {
+	let args: Vec<String> = env::args().collect();
+	let config = Config::load(&args[1..]).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(2);
+	});
+
+	let address = match config.role {
+		Role::Host(address) => address,
+		Role::Connect(_) | Role::Spectate(_) | Role::Replay(..) | Role::Local(_) => {
+			eprintln!("The server can only --host");
+			process::exit(2);
+		}
+	};
+
+	let server = Server::bind(&address).unwrap_or_else(|e| {
+		eprintln!("Could not listen on {}: {}", address, e);
+		process::exit(1);
+	});
+
+	let server = server.configure(config.settings).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(1);
+	});
+
+	let address = server.local_addr().unwrap_or_else(|e| {
+		eprintln!("Could not find the address listened on: {}", e);
+		process::exit(1);
+	});
+
+	println!("Listening on {}", address);
+	server.run();
+}
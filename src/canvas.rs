@@ -1,19 +1,264 @@
 extern crate piston_window;
 extern crate image as im;
 
-use std::{sync::{Arc, Mutex}, thread, cmp};
+use std::{sync::{Arc, Mutex}, thread};
 use std::sync::mpsc::{channel, Sender};
 use gfx_device_gl::{Factory, Resources, CommandBuffer};
 
 use piston_window::*;
 use im::Rgba;
+use crate::gallery;
+use crate::layout::{VIEW_SIZE, clear_view};
 
+/// Width and height of the picture in canvas pixels, unless the host picks another of
+/// `CANVAS_SIZES`.
+pub const CANVAS_SIZE: u32 = 100;
+
+/// The resolutions a host can play at; each divides the view evenly.
+pub const CANVAS_SIZES: &[u32] = &[100, 200, 400];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+	pub r: u8,
+	pub g: u8,
+	pub b: u8,
+}
+
+impl Color {
+	pub const fn new(r: u8, g: u8, b: u8) -> Self {
+		Color { r, g, b }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushShape {
+	Round,
+	Square,
+}
+
+/// The footprint of a stroke, `size` pixels across. A round brush can blend its edges into
+/// the picture (`antialias`) and bend its lines into a curve through the points before them
+/// (`smooth`); a square one is always stamped pixel by pixel along straight lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Brush {
+	pub size: u8,
+	pub shape: BrushShape,
+	pub antialias: bool,
+	pub smooth: bool,
+}
+
+impl Brush {
+	pub const MAX_SIZE: u8 = 12;
+
+	pub const fn new(size: u8, shape: BrushShape) -> Self {
+		Brush { size, shape, antialias: false, smooth: false }
+	}
+
+	pub const fn with_antialiasing(mut self, antialias: bool) -> Self {
+		self.antialias = antialias;
+		self
+	}
+
+	pub const fn with_smoothing(mut self, smooth: bool) -> Self {
+		self.smooth = smooth;
+		self
+	}
+
+	/// The size actually painted. A brush that never went through the protocol's checks, say
+	/// from a recording, is held to the sizes the tools offer, so it cannot take ages to draw.
+	fn painted_size(&self) -> u8 {
+		self.size.clamp(1, Self::MAX_SIZE)
+	}
+
+	/// The distance from its centre within which a round brush covers a pixel.
+	fn radius(&self) -> f64 {
+		self.painted_size() as f64 / 2.0
+	}
+
+	/// Where the centre of the brush sits relative to the pixel it is placed on: even sizes
+	/// have no middle pixel, so they are centred on a corner.
+	fn center_offset(&self) -> f64 {
+		if self.painted_size().is_multiple_of(2) { 0.5 } else { 0.0 }
+	}
+
+	/// How much of the pixel `distance` from the stroke's centre line the brush covers, from 0
+	/// to 1. Without anti-aliasing it is all or nothing, by the same rule as the stamped
+	/// footprint.
+	fn coverage(&self, distance: f64) -> f64 {
+		let radius = self.radius();
+		if self.antialias {
+			(radius + 0.5 - distance).clamp(0.0, 1.0)
+		} else if distance * distance <= (radius * radius - 0.5).max(0.0) {
+			1.0
+		} else {
+			0.0
+		}
+	}
+
+	/// Calls `func` for every offset from the brush centre that the brush covers.
+	fn for_each_offset<F>(&self, mut func: F) where F: FnMut(i32, i32) {
+		let size = self.painted_size() as i32;
+		let lo = -(size - 1) / 2;
+		let center = lo as f64 + (size - 1) as f64 / 2.0;
+		let radius = size as f64 / 2.0;
+
+		for dy in lo..lo + size {
+			for dx in lo..lo + size {
+				let (fx, fy) = (dx as f64 - center, dy as f64 - center);
+				// Shaving half a pixel off the radius keeps small round brushes from
+				// degenerating into squares.
+				if self.shape == BrushShape::Round && fx * fx + fy * fy > radius * radius - 0.5 {
+					continue;
+				}
+
+				func(dx, dy);
+			}
+		}
+	}
+}
+
+impl Default for Brush {
+	fn default() -> Self {
+		Brush::new(2, BrushShape::Round)
+	}
+}
+
+#[derive(Debug, Clone)]
 pub enum CanvasOperation {
-	Pixel(u32, u32, u8, u8, u8),
-	Line(u32, u32, u32, u32, u8, u8, u8),
-	Erase(u32, u32),
-	EraseLine(u32, u32, u32, u32),
+	Pixel(u32, u32, Color, Brush),
+	Line(u32, u32, u32, u32, Color, Brush),
+	Erase(u32, u32, Brush),
+	EraseLine(u32, u32, u32, u32, Brush),
+	Fill(u32, u32, Color),
 	Clear,
+	Undo,
+	Redo,
+	/// Writes the current picture to the drawings folder, named after the word.
+	Save(String),
+	/// Replaces the picture with a snapshot sent by the server.
+	Restore(Image),
+	/// Starts over on a blank canvas this many pixels across, unless it already is.
+	Resize(u32),
+}
+
+impl CanvasOperation {
+	/// Where the brush was left after drawing this, if it draws at all.
+	fn end(&self) -> Option<(u32, u32)> {
+		match *self {
+			CanvasOperation::Pixel(x, y, ..) | CanvasOperation::Erase(x, y, ..) => Some((x, y)),
+			CanvasOperation::Line(_, _, x, y, ..) | CanvasOperation::EraseLine(_, _, x, y, ..) => Some((x, y)),
+			_ => None,
+		}
+	}
+}
+
+pub type Image = im::ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// A position on the canvas in pixels; pixel (x, y) is centred on (x, y).
+type Point = (f64, f64);
+
+/// Smoothed lines are traced through a point at least this often, in pixels.
+const CURVE_STEP: f64 = 1.0;
+
+/// Points along the uniform Catmull-Rom curve from `p1` to `p2`, both included, shaped by the
+/// points either side of them.
+fn catmull_rom(p0: Point, p1: Point, p2: Point, p3: Point) -> Vec<Point> {
+	let length = (p2.0 - p1.0).hypot(p2.1 - p1.1);
+	let steps = (length / CURVE_STEP).ceil().max(1.0) as usize;
+	let at = |t: f64, a: f64, b: f64, c: f64, d: f64| {
+		0.5 * (2.0 * b + (c - a) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t * t + (3.0 * b - a - 3.0 * c + d) * t * t * t)
+	};
+
+	(0..=steps).map(|i| {
+		let t = i as f64 / steps as f64;
+		(at(t, p0.0, p1.0, p2.0, p3.0), at(t, p0.1, p1.1, p2.1, p3.1))
+	}).collect()
+}
+
+/// How much a round brush covers each pixel it reaches along a path, over the rectangle of
+/// pixels in its reach.
+struct Coverage {
+	left: u32,
+	top: u32,
+	width: u32,
+	height: u32,
+	values: Vec<f64>,
+}
+
+impl Coverage {
+	/// Each segment of the path only looks at the pixels in its own reach, so the work grows
+	/// with the length of the path rather than the area around it.
+	fn along(points: &[Point], brush: Brush, (width, height): (u32, u32)) -> Self {
+		let offset = brush.center_offset();
+		let points: Vec<Point> = points.iter().map(|(x, y)| (x + offset, y + offset)).collect();
+		let reach = brush.radius() + 1.0;
+		let span = |low: f64, high: f64, limit: u32| {
+			let start = ((low - reach).floor().max(0.0) as u32).min(limit);
+			let end = ((high + reach).ceil().max(0.0) as u32).min(limit);
+			start..end.max(start)
+		};
+
+		let (mut left, mut top, mut right, mut bottom) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+		for (x, y) in points.iter() {
+			(left, top, right, bottom) = (left.min(*x), top.min(*y), right.max(*x), bottom.max(*y));
+		}
+
+		let (columns, rows) = (span(left, right, width), span(top, bottom, height));
+		let mut coverage = Coverage {
+			left: columns.start,
+			top: rows.start,
+			width: columns.len() as u32,
+			height: rows.len() as u32,
+			values: vec![0.0; columns.len() * rows.len()],
+		};
+
+		let segments: Vec<(Point, Point)> = match points.as_slice() {
+			[only] => vec![(*only, *only)],
+			points => points.windows(2).map(|segment| (segment[0], segment[1])).collect(),
+		};
+
+		for (a, b) in segments {
+			for y in span(a.1.min(b.1), a.1.max(b.1), height) {
+				for x in span(a.0.min(b.0), a.0.max(b.0), width) {
+					let covered = brush.coverage(distance_to_segment((x as f64, y as f64), a, b));
+					let value = &mut coverage.values[((y - coverage.top) * coverage.width + x - coverage.left) as usize];
+					*value = value.max(covered);
+				}
+			}
+		}
+
+		coverage
+	}
+
+	fn at(&self, x: u32, y: u32) -> f64 {
+		if x < self.left || y < self.top || x >= self.left + self.width || y >= self.top + self.height {
+			return 0.0;
+		}
+
+		self.values[((y - self.top) * self.width + x - self.left) as usize]
+	}
+
+	/// Every pixel the brush covers at all, with how much.
+	fn pixels(&self) -> impl Iterator<Item = (u32, u32, f64)> + '_ {
+		self.values.iter().enumerate()
+			.filter(|(_, covered)| **covered > 0.0)
+			.map(|(i, covered)| (self.left + i as u32 % self.width, self.top + i as u32 / self.width, *covered))
+	}
+}
+
+/// The distance from `p` to the segment from `a` to `b`.
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+	let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+	let length_squared = dx * dx + dy * dy;
+	let t = if length_squared == 0.0 {
+		0.0
+	} else {
+		(((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+	};
+
+	(p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
 }
 
 fn line<F>(x1: f64, y1: f64, x2: f64, y2: f64, mut func: F) where F: FnMut(i32, i32) {
@@ -31,103 +276,363 @@ fn line<F>(x1: f64, y1: f64, x2: f64, y2: f64, mut func: F) where F: FnMut(i32,
 	}
 }
 
-pub struct GameCanvas {
-	pub op_sender: Sender<CanvasOperation>,
-	canvas: Arc<Mutex<im::ImageBuffer<Rgba<u8>, Vec<u8>>>>,
-	texture_context: TextureContext<Factory, Resources, CommandBuffer>,
-	texture: G2dTexture,
+/// The canvas pixels along with the strokes that produced them, so strokes can be undone.
+/// A stroke starts with a click operation (`Pixel`, `Erase`, `Fill`) and collects the
+/// `Line`/`EraseLine` drags that follow it, mirroring a mouse press until release.
+pub struct Picture {
+	image: Image,
+	/// The last snapshot restored, which strokes are repainted over; blank when there is none.
+	base: Option<Image>,
+	strokes: Vec<Vec<CanvasOperation>>,
+	undone: Vec<Vec<CanvasOperation>>,
 }
 
-impl GameCanvas {
-	pub fn new(window: &mut PistonWindow, width: u32, height: u32) -> Self {
-		let canvas = Arc::new(Mutex::new(
-			im::ImageBuffer::new(width, height)
-		));
-	
-		let mut texture_context = TextureContext {
-			factory: window.factory.clone(),
-			encoder: window.factory.create_command_buffer().into()
-		};
+impl Picture {
+	pub fn new(width: u32, height: u32) -> Self {
+		Picture {
+			image: Image::new(width, height),
+			base: None,
+			strokes: Vec::new(),
+			undone: Vec::new(),
+		}
+	}
 
-		let texture = Texture::from_image(
-			&mut texture_context,
-			&canvas.lock().unwrap(),
-			&TextureSettings::new().filter(Filter::Nearest)
-		).unwrap();
-		
-		let (sender, receiver) = channel();
-		let c = canvas.clone();
-		thread::spawn(move || {
-			loop {
-				let operation = receiver.recv().unwrap();
-				GameCanvas::process_operation(&mut c.lock().unwrap(), width, height, operation);
+	pub fn image(&self) -> &Image {
+		&self.image
+	}
+
+	pub fn apply(&mut self, operation: CanvasOperation) {
+		match operation {
+			CanvasOperation::Undo => {
+				if let Some(stroke) = self.strokes.pop() {
+					self.undone.push(stroke);
+					self.repaint();
+				}
+			},
+
+			CanvasOperation::Redo => {
+				if let Some(stroke) = self.undone.pop() {
+					Self::rasterize_stroke(&mut self.image, &stroke);
+					self.strokes.push(stroke);
+				}
+			},
+
+			CanvasOperation::Clear => {
+				self.base = None;
+				self.strokes.clear();
+				self.undone.clear();
+				Self::rasterize(&mut self.image, &operation, &[]);
+			},
+
+			// The snapshot may already include any of the strokes drawn so far, so they can no
+			// longer be undone. One of a different size is not ours to show.
+			CanvasOperation::Restore(image) => {
+				if image.dimensions() == self.image.dimensions() {
+					self.image = image.clone();
+					self.base = Some(image);
+					self.strokes.clear();
+					self.undone.clear();
+				}
+			},
+
+			CanvasOperation::Resize(size) => {
+				if self.image.dimensions() != (size, size) {
+					*self = Picture::new(size, size);
+					Self::rasterize(&mut self.image, &CanvasOperation::Clear, &[]);
+				}
+			},
+
+			CanvasOperation::Save(_) => {},
+
+			// A line that carries on from where the last stroke ends is part of it; any other
+			// starts a stroke of its own, to be undone by itself.
+			CanvasOperation::Line(x1, y1, ..) | CanvasOperation::EraseLine(x1, y1, ..) => {
+				match self.strokes.last_mut() {
+					Some(stroke) if stroke.last().and_then(CanvasOperation::end) == Some((x1, y1)) => {
+						Self::rasterize(&mut self.image, &operation, stroke);
+						stroke.push(operation);
+					},
+					_ => {
+						Self::rasterize(&mut self.image, &operation, &[]);
+						self.strokes.push(vec![operation]);
+						self.undone.clear();
+					},
+				}
+			},
+
+			_ => {
+				Self::rasterize(&mut self.image, &operation, &[]);
+				self.strokes.push(vec![operation]);
+				self.undone.clear();
 			}
-		});
-		
-		GameCanvas {
-			op_sender: sender,
-			canvas: canvas,
-			texture_context: texture_context,
-			texture: texture
 		}
 	}
 
-	fn erase(c: &mut im::ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, y: u32, width: u32, height: u32) {
-		let s = 2;
-		for x in cmp::max(0, x - s)..cmp::min(x + s + 1, width) {
-			for y in cmp::max(0, y - 1)..cmp::min(y + 2, height) {
-				c.put_pixel(x, y, im::Rgba([255, 255, 255, 255]));
+	/// Redraws the image from the base using the strokes that are still on the stack.
+	fn repaint(&mut self) {
+		match &self.base {
+			Some(base) => self.image = base.clone(),
+			None => Self::rasterize(&mut self.image, &CanvasOperation::Clear, &[]),
+		}
+
+		for stroke in self.strokes.iter() {
+			Self::rasterize_stroke(&mut self.image, stroke);
+		}
+	}
+
+	/// Paints a round brush along the path through `points`, as much as it covers each pixel
+	/// at its closest point on the path. Pixels the `under` path has already painted are only
+	/// topped up to that, so a stroke's lines do not darken where they meet.
+	fn trace(c: &mut Image, points: &[Point], under: &[Point], brush: Brush, pixel: Rgba<u8>) {
+		let coverage = Coverage::along(points, brush, c.dimensions());
+		let under = Coverage::along(under, brush, c.dimensions());
+		for (x, y, covered) in coverage.pixels() {
+			let painted = under.at(x, y);
+			if covered <= painted {
+				continue;
+			}
+
+			let covered = (covered - painted) / (1.0 - painted);
+			if covered >= 1.0 {
+				c.put_pixel(x, y, pixel);
+			} else {
+				let under = c.get_pixel_mut(x, y);
+				for (under, over) in under.0.iter_mut().zip(pixel.0) {
+					*under = (*under as f64 + (over as f64 - *under as f64) * covered).round() as u8;
+				}
 			}
 		}
 	}
 
-	fn draw(c: &mut im::ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, y: u32, width: u32, height: u32, r: u8, g: u8, b: u8) {
-		if x < width && y < height {
-			c.put_pixel(x, y, im::Rgba([r, g, b, 255]));
+	/// The path a line follows: straight, or with a smooth brush bent into a curve that joins
+	/// the line before it in the stroke without a corner. The point after the line is not
+	/// known yet, so the curve leaves it heading straight on.
+	fn path(x1: u32, y1: u32, x2: u32, y2: u32, brush: Brush, previous: Option<&CanvasOperation>) -> Vec<Point> {
+		let (p1, p2) = ((x1 as f64, y1 as f64), (x2 as f64, y2 as f64));
+		if !brush.smooth || brush.shape != BrushShape::Round {
+			return vec![p1, p2];
 		}
+
+		let p0 = match *previous.unwrap_or(&CanvasOperation::Clear) {
+			CanvasOperation::Line(px, py, x, y, ..) | CanvasOperation::EraseLine(px, py, x, y, ..) if (x, y) == (x1, y1) => {
+				(px as f64, py as f64)
+			},
+			_ => p1,
+		};
+
+		catmull_rom(p0, p1, p2, (2.0 * p2.0 - p1.0, 2.0 * p2.1 - p1.1))
 	}
 
-	fn process_operation(c: &mut im::ImageBuffer<Rgba<u8>, Vec<u8>>, width: u32, height: u32, operation: CanvasOperation) {
-		match operation {
-			CanvasOperation::Pixel(x, y, r, g, b) => {
-				Self::draw(c, x, y, width, height, r, g, b);
+	/// The path of the line before this one in the stroke, if this one carries on from its end
+	/// with the same brush and color; otherwise none.
+	fn joined_path(operation: &CanvasOperation, before: &[CanvasOperation]) -> Vec<Point> {
+		let (previous, rest) = match before.split_last() {
+			Some(split) => split,
+			None => return Vec::new(),
+		};
+
+		match (operation, previous) {
+			(&CanvasOperation::Line(x1, y1, _, _, color, brush), &CanvasOperation::Line(px1, py1, px2, py2, previous_color, previous_brush))
+				if (px2, py2) == (x1, y1) && previous_color == color && previous_brush == brush => {
+				Self::path(px1, py1, px2, py2, brush, rest.last())
+			},
+
+			(&CanvasOperation::EraseLine(x1, y1, _, _, brush), &CanvasOperation::EraseLine(px1, py1, px2, py2, previous_brush))
+				if (px2, py2) == (x1, y1) && previous_brush == brush => {
+				Self::path(px1, py1, px2, py2, brush, rest.last())
+			},
+
+			_ => Vec::new(),
+		}
+	}
+
+	/// Paints a stroke's operations in order, each line shaped by the ones before it.
+	fn rasterize_stroke(c: &mut Image, stroke: &[CanvasOperation]) {
+		for (i, operation) in stroke.iter().enumerate() {
+			Self::rasterize(c, operation, &stroke[..i]);
+		}
+	}
+
+	/// Paints the brush at a point, or along a path, over what the `under` path painted.
+	fn brush(c: &mut Image, points: &[Point], under: &[Point], brush: Brush, pixel: Rgba<u8>) {
+		match brush.shape {
+			BrushShape::Round => Self::trace(c, points, under, brush, pixel),
+			BrushShape::Square if points.len() == 1 => {
+				Self::paint(c, points[0].0 as i32, points[0].1 as i32, brush, pixel);
+			},
+			BrushShape::Square => {
+				for segment in points.windows(2) {
+					line(segment[0].0, segment[0].1, segment[1].0, segment[1].1, |x, y| Self::paint(c, x, y, brush, pixel));
+				}
+			},
+		}
+	}
+
+	fn paint(c: &mut Image, x: i32, y: i32, brush: Brush, pixel: Rgba<u8>) {
+		let (width, height) = c.dimensions();
+		brush.for_each_offset(|dx, dy| {
+			let (x, y) = (x + dx, y + dy);
+			if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+				c.put_pixel(x as u32, y as u32, pixel);
+			}
+		});
+	}
+
+	/// Scanline flood fill: repaints the region of identically colored pixels connected to (x, y).
+	fn fill(c: &mut Image, x: u32, y: u32, pixel: Rgba<u8>) {
+		let (width, height) = c.dimensions();
+		if x >= width || y >= height {
+			return;
+		}
+
+		let target = *c.get_pixel(x, y);
+		if target == pixel {
+			return;
+		}
+
+		let mut seeds = vec![(x, y)];
+		while let Some((x, y)) = seeds.pop() {
+			if *c.get_pixel(x, y) != target {
+				continue;
+			}
+
+			let mut left = x;
+			while left > 0 && *c.get_pixel(left - 1, y) == target {
+				left -= 1;
+			}
+
+			let mut right = x;
+			while right + 1 < width && *c.get_pixel(right + 1, y) == target {
+				right += 1;
+			}
+
+			for x in left..=right {
+				c.put_pixel(x, y, pixel);
+			}
+
+			let neighbours = [y.checked_sub(1), Some(y + 1).filter(|y| *y < height)];
+			for y in neighbours.into_iter().flatten() {
+				// One seed per run of target pixels on the neighbouring row.
+				let mut x = left;
+				while x <= right {
+					if *c.get_pixel(x, y) == target {
+						seeds.push((x, y));
+						while x <= right && *c.get_pixel(x, y) == target {
+							x += 1;
+						}
+					} else {
+						x += 1;
+					}
+				}
+			}
+		}
+	}
+
+	/// Paints one operation, after the operations `before` it in its stroke. A line is shaped
+	/// by the one before it when its brush is smooth.
+	fn rasterize(c: &mut Image, operation: &CanvasOperation, before: &[CanvasOperation]) {
+		let (width, height) = c.dimensions();
+		match *operation {
+			CanvasOperation::Pixel(x, y, color, brush) => {
+				Self::brush(c, &[(x as f64, y as f64)], &[], brush, Rgba([color.r, color.g, color.b, 255]));
+			},
+
+			CanvasOperation::Erase(x, y, brush) => {
+				Self::brush(c, &[(x as f64, y as f64)], &[], brush, WHITE);
 			},
 
-			CanvasOperation::Erase(x, y) => {
-				Self::erase(c, x, y, width, height);
+			CanvasOperation::Fill(x, y, color) => {
+				Self::fill(c, x, y, Rgba([color.r, color.g, color.b, 255]));
 			},
 
 			CanvasOperation::Clear => {
 				for x in 0..width {
 					for y in 0..height {
-						c.put_pixel(x, y, im::Rgba([255, 255, 255, 255]));
+						c.put_pixel(x, y, WHITE);
 					}
 				}
 			},
 
-			CanvasOperation::Line(x1, y1, x2, y2, r, g, b) => {
-				line(x1 as f64, y1 as f64, x2 as f64, y2 as f64, move |x, y| {
-					Self::draw(c, x as u32, y as u32, width, height, r, g, b);
-				});
+			CanvasOperation::Line(x1, y1, x2, y2, color, brush) => {
+				let path = Self::path(x1, y1, x2, y2, brush, before.last());
+				let under = Self::joined_path(operation, before);
+				Self::brush(c, &path, &under, brush, Rgba([color.r, color.g, color.b, 255]));
 			},
 
-			CanvasOperation::EraseLine(x1, y1, x2, y2) => {
-				line(x1 as f64, y1 as f64, x2 as f64, y2 as f64, move |x, y| {
-					Self::erase(c, x as u32, y as u32, width, height);
-				});
+			CanvasOperation::EraseLine(x1, y1, x2, y2, brush) => {
+				let path = Self::path(x1, y1, x2, y2, brush, before.last());
+				let under = Self::joined_path(operation, before);
+				Self::brush(c, &path, &under, brush, WHITE);
+			},
+
+			CanvasOperation::Undo | CanvasOperation::Redo | CanvasOperation::Save(_) |
+			CanvasOperation::Restore(_) | CanvasOperation::Resize(_) => {}
+		}
+	}
+}
+
+pub struct GameCanvas {
+	pub op_sender: Sender<CanvasOperation>,
+	picture: Arc<Mutex<Picture>>,
+	texture_context: TextureContext<Factory, Resources, CommandBuffer>,
+	texture: G2dTexture,
+}
+
+impl GameCanvas {
+	pub fn new(window: &mut PistonWindow, width: u32, height: u32) -> Self {
+		let picture = Arc::new(Mutex::new(Picture::new(width, height)));
+	
+		let mut texture_context = TextureContext {
+			factory: window.factory.clone(),
+			encoder: window.factory.create_command_buffer().into()
+		};
+
+		let texture = Texture::from_image(
+			&mut texture_context,
+			picture.lock().unwrap().image(),
+			&TextureSettings::new().filter(Filter::Nearest)
+		).unwrap();
+		
+		let (sender, receiver) = channel();
+		let p = picture.clone();
+		thread::spawn(move || {
+			loop {
+				match receiver.recv().unwrap() {
+					CanvasOperation::Save(word) => {
+						if let Err(e) = gallery::save_drawing(p.lock().unwrap().image(), &word) {
+							eprintln!("Could not save drawing of '{}': {}", word, e);
+						}
+					},
+
+					operation => p.lock().unwrap().apply(operation),
+				}
 			}
+		});
+		
+		GameCanvas {
+			op_sender: sender,
+			picture,
+			texture_context,
+			texture
 		}
 	}
 
 	pub fn pre_render(&mut self) {
-		self.texture.update(&mut self.texture_context, &self.canvas.lock().unwrap()).unwrap();
+		let picture = self.picture.lock().unwrap();
+		let image = picture.image();
+		if self.texture.get_size() == image.dimensions() {
+			self.texture.update(&mut self.texture_context, image).unwrap();
+		} else {
+			self.texture = Texture::from_image(&mut self.texture_context, image, &TextureSettings::new().filter(Filter::Nearest)).unwrap();
+		}
 	}
 
 	pub fn render(&mut self, c: Context, g: &mut G2d, device: &mut gfx_device_gl::Device) {
 		self.texture_context.encoder.flush(device);
 		
-		clear([1.0; 4], g);
+		clear_view(c, g);
 
-		image(&self.texture, c.transform.scale(8.0, 8.0), g);
+		let (width, height) = self.texture.get_size();
+		image(&self.texture, c.transform.scale(VIEW_SIZE / width as f64, VIEW_SIZE / height as f64), g);
 	}
 }
\ No newline at end of file
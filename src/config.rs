@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use crate::canvas::{CANVAS_SIZE, CANVAS_SIZES};
+use crate::server::words::Difficulty;
+
+/// Read from the working directory when present; command line options take precedence.
+pub const CONFIG_FILE: &str = "pictionary.toml";
+
+pub const USAGE: &str = "usage: pictionary (--host address | --connect address | --spectate address | \
+	--local name,name,... | --replay file [--speed factor])
+	[--name name] [--record file] [--round-seconds seconds] [--choices count] [--rounds count] [--words pack,...] \
+	[--difficulty easy|medium|hard] [--hints percent,...|none] [--canvas-size 100|200|400]";
+
+/// How the host runs its rounds. The first three are pushed to every guest in
+/// `GameMessage::Settings` and the canvas size in the server's `Hello`; the rest only matter
+/// to the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+	pub round_seconds: u32,
+	/// How many words the drawer picks from.
+	pub choices: u32,
+	/// Rounds in a match, 0 to keep playing until everyone leaves.
+	pub rounds: u32,
+	/// When guessers get another letter, in percent of the round elapsed.
+	pub hints: Vec<u32>,
+	/// Word packs or categories to play with; every pack when empty.
+	pub words: Vec<String>,
+	pub difficulty: Option<Difficulty>,
+	/// Width and height of the canvas, in pixels.
+	pub canvas_size: u32,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Settings {
+			round_seconds: 100,
+			choices: 3,
+			rounds: 0,
+			hints: vec![50, 75],
+			words: Vec::new(),
+			difficulty: None,
+			canvas_size: CANVAS_SIZE,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Role {
+	/// Run the server on this address and join it.
+	Host(String),
+	Connect(String),
+	/// Watch the game at this address without playing.
+	Spectate(String),
+	/// Play back a recording at this multiple of its original pace.
+	Replay(String, f64),
+	/// Everyone plays in this one window, taking turns at the keyboard.
+	Local(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+	pub role: Role,
+	pub name: String,
+	/// Where to record the session, if anywhere.
+	pub record: Option<String>,
+	pub settings: Settings,
+}
+
+/// Every option, as it appears in the config file or on the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct Options {
+	host: Option<String>,
+	connect: Option<String>,
+	spectate: Option<String>,
+	name: Option<String>,
+	record: Option<String>,
+	replay: Option<String>,
+	local: Option<Vec<String>>,
+	speed: Option<f64>,
+	round_seconds: Option<u32>,
+	choices: Option<u32>,
+	rounds: Option<u32>,
+	words: Option<Vec<String>>,
+	difficulty: Option<Difficulty>,
+	hints: Option<Vec<u32>>,
+	canvas_size: Option<u32>,
+}
+
+impl Options {
+	fn from_args(args: &[String]) -> Result<Self, String> {
+		fn number(flag: &str, value: &str) -> Result<u32, String> {
+			value.parse().map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+		}
+
+		let mut options = Options::default();
+		let mut args = args.iter();
+		while let Some(flag) = args.next() {
+			let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+			match flag.as_str() {
+				"--host" => options.host = Some(value.clone()),
+				"--connect" => options.connect = Some(value.clone()),
+				"--spectate" => options.spectate = Some(value.clone()),
+				"--name" => options.name = Some(value.clone()),
+				"--record" => options.record = Some(value.clone()),
+				"--replay" => options.replay = Some(value.clone()),
+				"--local" => options.local = Some(value.split(',').map(|name| name.trim().to_owned()).collect()),
+				"--speed" => {
+					options.speed = Some(value.parse().map_err(|_| format!("{} expects a number, got '{}'", flag, value))?);
+				},
+				"--round-seconds" => options.round_seconds = Some(number(flag, value)?),
+				"--choices" => options.choices = Some(number(flag, value)?),
+				"--rounds" => options.rounds = Some(number(flag, value)?),
+				"--canvas-size" => options.canvas_size = Some(number(flag, value)?),
+				"--words" => options.words = Some(value.split(',').map(|pack| pack.trim().to_owned()).collect()),
+				"--difficulty" => options.difficulty = Some(value.parse()?),
+				"--hints" if value == "none" => options.hints = Some(Vec::new()),
+				"--hints" => {
+					options.hints = Some(value.split(',')
+						.map(|percent| number(flag, percent.trim()))
+						.collect::<Result<_, _>>()?);
+				},
+				_ => return Err(format!("Unknown option '{}'\n{}", flag, USAGE)),
+			}
+		}
+
+		Ok(options)
+	}
+
+	/// Whether any of the options that pick a role is set.
+	fn has_role(&self) -> bool {
+		self.host.is_some() || self.connect.is_some() || self.spectate.is_some() ||
+			self.replay.is_some() || self.local.is_some()
+	}
+
+	/// Fills in anything not set here from `fallback`. The role comes whole from one side:
+	/// if one is picked here, none of the role options in `fallback` apply.
+	fn or(self, fallback: Options) -> Options {
+		let fallback = if self.has_role() {
+			Options { host: None, connect: None, spectate: None, replay: None, local: None, ..fallback }
+		} else {
+			fallback
+		};
+
+		Options {
+			host: self.host.or(fallback.host),
+			connect: self.connect.or(fallback.connect),
+			spectate: self.spectate.or(fallback.spectate),
+			name: self.name.or(fallback.name),
+			record: self.record.or(fallback.record),
+			replay: self.replay.or(fallback.replay),
+			local: self.local.or(fallback.local),
+			speed: self.speed.or(fallback.speed),
+			round_seconds: self.round_seconds.or(fallback.round_seconds),
+			choices: self.choices.or(fallback.choices),
+			rounds: self.rounds.or(fallback.rounds),
+			words: self.words.or(fallback.words),
+			difficulty: self.difficulty.or(fallback.difficulty),
+			hints: self.hints.or(fallback.hints),
+			canvas_size: self.canvas_size.or(fallback.canvas_size),
+		}
+	}
+}
+
+impl Config {
+	/// Resolves the configuration from the command line (without the program name) and
+	/// `pictionary.toml`, if there is one.
+	pub fn load(args: &[String]) -> Result<Self, String> {
+		let file = if Path::new(CONFIG_FILE).exists() {
+			Some(fs::read_to_string(CONFIG_FILE).map_err(|e| format!("{}: {}", CONFIG_FILE, e))?)
+		} else {
+			None
+		};
+
+		Config::parse(args, file.as_deref())
+	}
+
+	/// Resolves the configuration from the command line and the contents of a config file.
+	pub fn parse(args: &[String], file: Option<&str>) -> Result<Self, String> {
+		let file = match file {
+			Some(file) => toml::from_str(file).map_err(|e| format!("{}: {}", CONFIG_FILE, e))?,
+			None => Options::default(),
+		};
+
+		let options = Options::from_args(args)?.or(file);
+		let speed = options.speed.unwrap_or(1.0);
+		let role = match (options.host, options.connect, options.spectate, options.replay, options.local) {
+			(Some(address), None, None, None, None) => Role::Host(address),
+			(None, Some(address), None, None, None) => Role::Connect(address),
+			(None, None, Some(address), None, None) => Role::Spectate(address),
+			(None, None, None, Some(file), None) => Role::Replay(file, speed),
+			(None, None, None, None, Some(names)) => Role::Local(names),
+			(None, None, None, None, None) => {
+				return Err(format!("One of --host, --connect, --spectate, --local or --replay is required\n{}", USAGE));
+			},
+			_ => return Err(format!("Use only one of --host, --connect, --spectate, --local and --replay\n{}", USAGE)),
+		};
+
+		if let Role::Local(names) = &role {
+			let mut unique = names.clone();
+			unique.sort();
+			unique.dedup();
+			if names.len() < 2 || unique.len() < names.len() || names.iter().any(String::is_empty) {
+				return Err("--local needs two or more different names".to_owned());
+			}
+		}
+
+		if !(speed > 0.0 && speed.is_finite()) {
+			return Err("--speed must be a positive number".to_owned());
+		}
+
+		let defaults = Settings::default();
+		let settings = Settings {
+			round_seconds: options.round_seconds.unwrap_or(defaults.round_seconds),
+			choices: options.choices.unwrap_or(defaults.choices),
+			rounds: options.rounds.unwrap_or(defaults.rounds),
+			hints: options.hints.unwrap_or(defaults.hints),
+			words: options.words.unwrap_or(defaults.words),
+			difficulty: options.difficulty.or(defaults.difficulty),
+			canvas_size: options.canvas_size.unwrap_or(defaults.canvas_size),
+		};
+
+		if settings.round_seconds == 0 {
+			return Err("--round-seconds must be at least 1".to_owned());
+		}
+
+		// Words are picked with the number keys.
+		if !(1..=9).contains(&settings.choices) {
+			return Err("--choices must be between 1 and 9".to_owned());
+		}
+
+		if settings.hints.iter().any(|percent| *percent > 100) {
+			return Err("--hints expects percentages from 0 to 100".to_owned());
+		}
+
+		if !CANVAS_SIZES.contains(&settings.canvas_size) {
+			return Err("--canvas-size must be 100, 200 or 400".to_owned());
+		}
+
+		Ok(Config {
+			role,
+			name: options.name.unwrap_or_default(),
+			record: options.record,
+			settings,
+		})
+	}
+}
@@ -0,0 +1,97 @@
+extern crate image as im;
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use im::imageops::{self, FilterType};
+use im::Rgba;
+use crate::canvas::Image;
+use crate::layout::VIEW_SIZE;
+
+/// Folder, relative to the working directory, that finished drawings are written to.
+pub const DRAWINGS_FOLDER: &str = "drawings";
+
+const TILE_SIZE: u32 = 200;
+const TILE_PADDING: u32 = 10;
+
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Writes the picture, upscaled to its on-screen size, to `drawings/<timestamp>_<word>.png`.
+pub fn save_drawing(image: &Image, word: &str) -> Result<PathBuf, Box<dyn Error>> {
+	fs::create_dir_all(DRAWINGS_FOLDER)?;
+
+	let name: String = word.chars()
+		.map(|c| if c.is_alphanumeric() { c } else { '-' })
+		.collect();
+	let path = Path::new(DRAWINGS_FOLDER).join(format!("{}_{}.png", now(), name));
+
+	// The same whole factor the canvas is scaled by to fill the view.
+	let (width, height) = image.dimensions();
+	let scale = (VIEW_SIZE as u32 / width.max(1)).max(1);
+	imageops::resize(image, width * scale, height * scale, FilterType::Nearest).save(&path)?;
+
+	Ok(path)
+}
+
+/// The drawings in `folder` saved at or after `since` (seconds since the epoch), oldest first.
+pub fn drawings_since(folder: &Path, since: u64) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	let mut drawings = Vec::new();
+	for entry in fs::read_dir(folder)? {
+		let path = entry?.path();
+		if path.extension().is_none_or(|ext| ext != "png") {
+			continue;
+		}
+
+		let timestamp = path.file_stem()
+			.and_then(|stem| stem.to_str())
+			.and_then(|stem| stem.split_once('_'))
+			.and_then(|(timestamp, _)| timestamp.parse::<u64>().ok());
+
+		if let Some(timestamp) = timestamp.filter(|t| *t >= since) {
+			drawings.push((timestamp, path));
+		}
+	}
+
+	drawings.sort();
+	Ok(drawings.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Lays the drawings out on a roughly square grid of fixed size tiles.
+pub fn contact_sheet(drawings: &[PathBuf]) -> Result<Image, Box<dyn Error>> {
+	let columns = (drawings.len() as f64).sqrt().ceil().max(1.0) as u32;
+	let rows = (drawings.len() as u32).div_ceil(columns).max(1);
+	let width = columns * TILE_SIZE + (columns + 1) * TILE_PADDING;
+	let height = rows * TILE_SIZE + (rows + 1) * TILE_PADDING;
+
+	let mut sheet = Image::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+	for (i, path) in drawings.iter().enumerate() {
+		let tile = im::open(path)?.to_rgba8();
+		let tile = imageops::resize(&tile, TILE_SIZE, TILE_SIZE, FilterType::Triangle);
+
+		let (column, row) = (i as u32 % columns, i as u32 / columns);
+		let x = TILE_PADDING + column * (TILE_SIZE + TILE_PADDING);
+		let y = TILE_PADDING + row * (TILE_SIZE + TILE_PADDING);
+		imageops::overlay(&mut sheet, &tile, x as i64, y as i64);
+	}
+
+	Ok(sheet)
+}
+
+/// Builds a contact sheet of the drawings from the last `hours` hours and saves it next to
+/// them as `drawings/gallery_<timestamp>.png`.
+pub fn save_gallery(hours: u64) -> Result<PathBuf, Box<dyn Error>> {
+	let folder = Path::new(DRAWINGS_FOLDER);
+	let drawings = drawings_since(folder, now().saturating_sub(hours * 60 * 60))?;
+	if drawings.is_empty() {
+		return Err(format!("No drawings from the last {} hours in '{}'", hours, DRAWINGS_FOLDER).into());
+	}
+
+	let path = folder.join(format!("gallery_{}.png", now()));
+	contact_sheet(&drawings)?.save(&path)?;
+
+	Ok(path)
+}
@@ -1,17 +1,30 @@
 extern crate piston_window;
 extern crate image as im;
 
-use std::net::TcpStream;
+use std::io;
+use std::time::{Duration, Instant};
 
 use crate::game::game_action::GameAction;
-use crate::canvas::CanvasOperation;
-use crate::game::message::GameMessage;
+use crate::game::scoreboard::Scoreboard;
+use crate::canvas::{Brush, CANVAS_SIZE, CanvasOperation, Color};
+use crate::config::Settings;
+use crate::layout::Layout;
+use crate::message::{Capabilities, GameMessage, MAX_POLYLINE_POINTS, capability};
+use crate::pictlog::Recorder;
+use crate::transport::Transport;
 use piston_window::*;
 use std::sync::mpsc::Sender;
 
+/// How many chat lines are kept; older ones are dropped.
+const CHAT_HISTORY: usize = 50;
+
+/// How long the lines of a drag are gathered before they are sent as one polyline.
+const STROKE_INTERVAL: Duration = Duration::from_millis(50);
+
 enum KeyboardButtonType {
 	Letter(char),
 	Number(u8),
+	Symbol(char),
 	Enter,
 	Backspace,
 	Other
@@ -58,6 +71,10 @@ impl KeyboardButtonType {
 			Key::D8 => KeyboardButtonType::Number(8),
 			Key::D9 => KeyboardButtonType::Number(9),
 
+			Key::LeftBracket => KeyboardButtonType::Symbol('['),
+			Key::RightBracket => KeyboardButtonType::Symbol(']'),
+			Key::Space => KeyboardButtonType::Symbol(' '),
+
 			Key::Return => KeyboardButtonType::Enter,
 
 			Key::Backspace => KeyboardButtonType::Backspace,
@@ -72,33 +89,162 @@ struct EventState {
 	last_mouse_pos: (u32, u32),
 	current_mouse_pos: (u32, u32),
 	left_mouse_down: bool,
-	right_mouse_down: bool
+	right_mouse_down: bool,
+	ctrl_down: bool,
+}
+
+/// Lines of the drawer's drag not sent yet, and when the first of them was drawn.
+struct PendingStroke {
+	/// What the lines are painted with; none when they erase.
+	color: Option<Color>,
+	brush: Brush,
+	points: Vec<(u16, u16)>,
+	started: Instant,
 }
 
 pub struct Communications {
-	stream: Option<TcpStream>,
+	transport: Option<Box<dyn Transport>>,
+	capabilities: Capabilities,
+	/// Width and height of the canvas the server picked.
+	canvas_size: u32,
+	/// Whether the local client only watches the game.
+	spectating: bool,
+	local_id: u32,
+	roster: Vec<(u32, String)>,
+	scores: Vec<(u32, u32)>,
+	chat: Vec<String>,
+	settings: Settings,
+	drawer_id: Option<u32>,
 	action_sender: Sender<GameAction>,
 	canvas_op_sender: Sender<CanvasOperation>,
+	recorder: Option<Recorder>,
+	stroke: Option<PendingStroke>,
+	/// Where the view was last drawn in the window, to map the cursor onto the canvas.
+	layout: Layout,
 	event_state: EventState,
 }
 
 impl Communications {
-	pub fn new(stream: Option<TcpStream>, action_sender: Sender<GameAction>, canvas_op_sender: Sender<CanvasOperation>) -> Self {
+	pub fn new(transport: Option<Box<dyn Transport>>, action_sender: Sender<GameAction>, canvas_op_sender: Sender<CanvasOperation>) -> Self {
 		Communications { 
-			stream: stream, 
-			action_sender: action_sender, 
-			canvas_op_sender: canvas_op_sender, 
+			transport, 
+			capabilities: 0,
+			canvas_size: CANVAS_SIZE,
+			spectating: false,
+			local_id: 0,
+			roster: Vec::new(),
+			scores: Vec::new(),
+			chat: Vec::new(),
+			settings: Settings::default(),
+			drawer_id: None,
+			action_sender, 
+			canvas_op_sender, 
+			recorder: None,
+			stroke: None,
+			layout: Layout::default(),
 			event_state: EventState {
 				last_mouse_pos: (0, 0),
 				current_mouse_pos: (0, 0),
 				left_mouse_down: false, 
-				right_mouse_down: false 
+				right_mouse_down: false,
+				ctrl_down: false,
 			} 
 		}
 	}
 
-	pub fn set_stream(&mut self, stream: TcpStream) {
-		self.stream = Some(stream);
+	/// Starts talking to the server over `transport` on the terms agreed in its hello, with the
+	/// canvas reallocated if the server picked a different size.
+	pub fn set_transport(&mut self, transport: Box<dyn Transport>, capabilities: Capabilities, canvas_size: u32) {
+		self.transport = Some(transport);
+		self.capabilities = capabilities;
+		self.canvas_size = canvas_size;
+		self.send_canvas_op(CanvasOperation::Resize(canvas_size));
+	}
+
+	pub fn canvas_size(&self) -> u32 {
+		self.canvas_size
+	}
+
+	/// Whether the server negotiated every capability in `capabilities`.
+	pub fn supports(&self, capabilities: Capabilities) -> bool {
+		self.capabilities & capabilities == capabilities
+	}
+
+	pub fn spectating(&self) -> bool {
+		self.spectating
+	}
+
+	pub fn set_spectating(&mut self, spectating: bool) {
+		self.spectating = spectating;
+	}
+
+	pub fn local_id(&self) -> u32 {
+		self.local_id
+	}
+
+	pub fn roster(&self) -> &[(u32, String)] {
+		&self.roster
+	}
+
+	pub fn set_roster(&mut self, local_id: u32, roster: Vec<(u32, String)>) {
+		self.local_id = local_id;
+		self.roster = roster;
+	}
+
+	/// The name the server knows the local player by, once it has sent the roster.
+	pub fn local_name(&self) -> Option<String> {
+		self.roster.iter()
+			.find(|(id, _)| *id == self.local_id)
+			.map(|(_, name)| name.clone())
+	}
+
+	/// The player's name, or a placeholder if they are not on the roster.
+	pub fn player_name(&self, id: u32) -> String {
+		self.roster.iter()
+			.find(|(player_id, _)| *player_id == id)
+			.map_or_else(|| format!("Player {}", id), |(_, name)| name.clone())
+	}
+
+	pub fn chat(&self) -> &[String] {
+		&self.chat
+	}
+
+	pub fn push_chat(&mut self, line: String) {
+		self.chat.push(line);
+		if self.chat.len() > CHAT_HISTORY {
+			self.chat.remove(0);
+		}
+	}
+
+	pub fn remove_player(&mut self, id: u32) {
+		self.roster.retain(|(player_id, _)| *player_id != id);
+	}
+
+	pub fn set_scores(&mut self, scores: Vec<(u32, u32)>) {
+		self.scores = scores;
+	}
+
+	pub fn scoreboard(&self) -> Scoreboard {
+		Scoreboard::new(&self.roster, &self.scores)
+	}
+
+	/// The host's settings, or the defaults if the host has not sent any.
+	pub fn settings(&self) -> &Settings {
+		&self.settings
+	}
+
+	pub fn set_settings(&mut self, round_seconds: u32, choices: u32, rounds: u32) {
+		self.settings.round_seconds = round_seconds;
+		self.settings.choices = choices;
+		self.settings.rounds = rounds;
+	}
+
+	pub fn drawer_id(&self) -> Option<u32> {
+		self.drawer_id
+	}
+
+	pub fn set_drawer_id(&mut self, drawer_id: Option<u32>) {
+		self.drawer_id = drawer_id;
 	}
 
 	pub fn send_action(&mut self, action: GameAction) {
@@ -109,14 +255,112 @@ impl Communications {
 		self.canvas_op_sender.send(op).unwrap();
 	}
 
+	/// Sends a message to the server, after the lines of the stroke gathered so far so that
+	/// they arrive in the order they were drawn.
 	pub fn send_message(&mut self, message: GameMessage) {
-		if let Some(mut stream) = self.stream.as_mut() {
-			message.send(&mut stream);
+		self.send_stroke();
+		self.write(message);
+	}
+
+	/// Sends a line of the drawer's stroke. If the server reads polylines, the line is held
+	/// back with the ones before it and they are all sent together once `STROKE_INTERVAL` has
+	/// passed, the mouse is released or another message is sent.
+	pub fn send_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Color, brush: Brush) {
+		if self.supports(capability::POLYLINE) {
+			self.gather_line(x1, y1, x2, y2, Some(color), brush);
+		} else {
+			self.send_message(GameMessage::DrawLine(x1, y1, x2, y2, color, brush));
+		}
+	}
+
+	/// Sends a line of an erasing stroke, held back the same way as `send_line`.
+	pub fn send_erase_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, brush: Brush) {
+		if self.supports(capability::POLYLINE) {
+			self.gather_line(x1, y1, x2, y2, None, brush);
+		} else {
+			self.send_message(GameMessage::EraseLine(x1, y1, x2, y2, brush));
+		}
+	}
+
+	/// Adds the line to the pending stroke if it carries on from it, otherwise sends that
+	/// stroke and starts a new one.
+	fn gather_line(&mut self, x1: u32, y1: u32, x2: u32, y2: u32, color: Option<Color>, brush: Brush) {
+		let (start, end) = ((x1 as u16, y1 as u16), (x2 as u16, y2 as u16));
+		match self.stroke.as_mut() {
+			Some(stroke) if stroke.color == color && stroke.brush == brush &&
+				stroke.points.last() == Some(&start) && stroke.points.len() < MAX_POLYLINE_POINTS => {
+				stroke.points.push(end);
+			},
+
+			_ => {
+				self.send_stroke();
+				self.stroke = Some(PendingStroke {
+					color,
+					brush,
+					points: vec![start, end],
+					started: Instant::now(),
+				});
+			},
+		}
+	}
+
+	fn send_stroke(&mut self) {
+		match self.stroke.take() {
+			Some(PendingStroke { color: Some(color), brush, points, .. }) => self.write(GameMessage::Polyline { color, brush, points }),
+			Some(PendingStroke { color: None, brush, points, .. }) => self.write(GameMessage::ErasePolyline { brush, points }),
+			None => {},
+		}
+	}
+
+	/// Sends what is left of the stroke and anything the transport is holding back.
+	pub fn flush(&mut self) {
+		self.send_stroke();
+		if let Some(transport) = self.transport.as_ref() {
+			let _ = transport.flush();
+		}
+	}
+
+	fn write(&mut self, message: GameMessage) {
+		if let Some(transport) = self.transport.as_ref() {
+			if self.supports(message.required_capabilities()) {
+				let _ = transport.send(&message);
+				if let Some(recorder) = self.recorder.as_mut() {
+					let recorded = recorder.sent(&message);
+					self.stop_recording_on_error(recorded);
+				}
+			}
+		}
+	}
+
+	pub fn set_recorder(&mut self, recorder: Recorder) {
+		self.recorder = Some(recorder);
+	}
+
+	/// Adds a message from the server to the recording, if the session is being recorded.
+	pub fn record(&mut self, message: &GameMessage) {
+		if let Some(recorder) = self.recorder.as_mut() {
+			let recorded = recorder.received(message);
+			self.stop_recording_on_error(recorded);
+		}
+	}
+
+	fn stop_recording_on_error(&mut self, recorded: io::Result<()>) {
+		if let Err(e) = recorded {
+			eprintln!("Stopped recording: {}", e);
+			self.recorder = None;
 		}
 	}
 
 	fn process_keyboard_button_event(&mut self, keyboard_button_type: KeyboardButtonType) {
 		match keyboard_button_type {
+			KeyboardButtonType::Letter('z') if self.event_state.ctrl_down => {
+				self.send_action(GameAction::UndoShortcut);
+			},
+
+			KeyboardButtonType::Letter('y') if self.event_state.ctrl_down => {
+				self.send_action(GameAction::RedoShortcut);
+			},
+
 			KeyboardButtonType::Letter(char) => {
 				self.send_action(GameAction::TypeLetter(char));
 			},
@@ -125,6 +369,10 @@ impl Communications {
 				self.send_action(GameAction::TypeNumber(num));
 			},
 
+			KeyboardButtonType::Symbol(char) => {
+				self.send_action(GameAction::TypeSymbol(char));
+			},
+
 			KeyboardButtonType::Enter => {
 				self.send_action(GameAction::Enter);
 			},
@@ -140,6 +388,10 @@ impl Communications {
 	fn process_button_event(&mut self, args: ButtonArgs) {
 		match args.button {
 			Button::Keyboard(key) => {
+				if let Key::LCtrl | Key::RCtrl = key {
+					self.event_state.ctrl_down = matches!(args.state, ButtonState::Press);
+				}
+
 				if let ButtonState::Press = args.state {
 					self.process_keyboard_button_event(KeyboardButtonType::from_key(key));
 				}
@@ -156,6 +408,8 @@ impl Communications {
 						self.event_state.left_mouse_down = state;
 						if state {
 							self.send_action(GameAction::LeftClick(self.event_state.current_mouse_pos.0, self.event_state.current_mouse_pos.1))
+						} else {
+							self.send_action(GameAction::MouseRelease);
 						}
 					},
 					
@@ -163,6 +417,8 @@ impl Communications {
 						self.event_state.right_mouse_down = state;
 						if state {
 							self.send_action(GameAction::RightClick(self.event_state.current_mouse_pos.0, self.event_state.current_mouse_pos.1))
+						} else {
+							self.send_action(GameAction::MouseRelease);
 						}
 					},
 					
@@ -175,9 +431,12 @@ impl Communications {
 	}
 
 	pub fn process_event(&mut self, e: Event) {
-		if let Some(p) = e.mouse_cursor_args() {
+		if let Some(args) = e.render_args() {
+			self.layout = Layout::new(args.window_size);
+		}
+
+		if let Some(c) = e.mouse_cursor_args().and_then(|p| self.layout.to_canvas(p, self.canvas_size, self.canvas_size)) {
 			let l = self.event_state.current_mouse_pos;
-			let c = ((p[0] as u32) / 8, (p[1] as u32) / 8);
 
 			self.event_state.last_mouse_pos = l;
 			self.event_state.current_mouse_pos = c;
@@ -192,16 +451,21 @@ impl Communications {
 		}
 
 		match e {
-			Event::Input(input, _) => {
-				if let Input::Button(args) = input {
-					self.process_button_event(args);
-				};
+			Event::Input(Input::Button(args), _) => {
+				self.process_button_event(args);
 			},
 
-			Event::Loop(loop_event) => {
-				if let Loop::Update(update_args) = loop_event {
-					self.action_sender.send(GameAction::Update(update_args.dt)).unwrap();
+			Event::Loop(Loop::Update(update_args)) => {
+				if self.stroke.as_ref().is_some_and(|stroke| stroke.started.elapsed() >= STROKE_INTERVAL) {
+					self.send_stroke();
 				}
+
+				// Whatever was sent since the last update goes out together.
+				if let Some(transport) = self.transport.as_ref() {
+					let _ = transport.flush();
+				}
+
+				self.action_sender.send(GameAction::Update(update_args.dt)).unwrap();
 			}
 			_ => {},
 		};
@@ -1,23 +1,45 @@
+use crate::canvas::{Brush, Color};
+use crate::message::{RoundResult, Verdict};
+
 #[derive(Debug)]
 pub enum GameAction {
 	Update(f64),
-	Tick,
 	TypeNumber(u8),
 	TypeLetter(char),
+	TypeSymbol(char),
 	DeleteLetter,
 	Enter,
 	SetTimeRemaining(u32),
 	SetWordSkeleton(String),
-	Draw(u32, u32),
-	DrawLine(u32, u32, u32, u32),
-	Erase(u32, u32),
-	EraseLine(u32, u32, u32, u32),
+	Answer(String),
+	Draw(u32, u32, Color, Brush),
+	DrawLine(u32, u32, u32, u32, Color, Brush),
+	Erase(u32, u32, Brush),
+	EraseLine(u32, u32, u32, u32, Brush),
+	Polyline(Color, Brush, Vec<(u16, u16)>),
+	ErasePolyline(Brush, Vec<(u16, u16)>),
+	Fill(u32, u32, Color),
+	Undo,
+	Redo,
+	UndoShortcut,
+	RedoShortcut,
 	LeftClick(u32, u32),
 	LeftClickDrag(u32, u32, u32, u32),
 	RightClick(u32, u32),
 	RightClickDrag(u32, u32, u32, u32),
-	Guess(String),
-	GuessResult(Option<String>),
+	MouseRelease,
+	Guess(u32, String),
+	GuessResult(u32, Verdict),
 	GameOver(String),
-	SwapRoles,
+	SwapRoles(u32),
+	PlayerLeft(u32),
+	Roster(u32, Vec<(u32, String)>),
+	WordChoices(Vec<String>),
+	ScoreUpdate(Vec<(u32, u32)>),
+	Settings(u32, u32, u32),
+	MatchOver(Vec<RoundResult>),
+	CanvasSnapshot(u32, u32, Vec<u8>),
+	Reconnecting(u32),
+	Reconnected,
+	Disconnected(String),
 }
\ No newline at end of file
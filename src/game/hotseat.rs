@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use piston_window::*;
+use crate::canvas::GameCanvas;
+use crate::config::Settings;
+use crate::game::Game;
+use crate::game::text_util::{Glyphs, center_text};
+use crate::layout::{CENTER, clear_view};
+use crate::server::Server;
+
+/// One of the players sharing the window, with a game and a canvas of their own just as if
+/// they were playing on their own machine.
+struct Seat {
+	name: String,
+	game: Arc<Mutex<Game>>,
+	canvas: GameCanvas,
+}
+
+/// Several players taking turns at one window and keyboard, each connected to an in-process
+/// server. Only one seat is shown at a time; in between, a blank screen asks for the keyboard
+/// to be passed on so nobody sees another player's screen.
+pub struct Hotseat {
+	seats: Vec<Seat>,
+	active: usize,
+	/// The seat the keyboard is being passed to, until they say they are ready.
+	passing_to: Option<usize>,
+	drawer: Option<usize>,
+}
+
+impl Hotseat {
+	pub fn new(window: &mut PistonWindow, names: Vec<String>, settings: Settings) -> Result<Self, Box<dyn Error>> {
+		let size = settings.canvas_size;
+		let mut server = Server::local().configure(settings)?;
+		let seats = names.into_iter().map(|name| {
+			let canvas = GameCanvas::new(window, size, size);
+			let game = Game::local(name.clone(), server.connect_local(), canvas.op_sender.clone());
+			Seat { name, game, canvas }
+		}).collect();
+
+		thread::spawn(move || server.run());
+
+		Ok(Hotseat {
+			seats,
+			active: 0,
+			passing_to: None,
+			drawer: None,
+		})
+	}
+
+	pub fn pre_render(&mut self) {
+		self.seats[self.active].canvas.pre_render();
+	}
+
+	pub fn render(&mut self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, device: &mut gfx_device_gl::Device) {
+		if let Some(next) = self.passing_to {
+			clear_view(c, g);
+			let line = format!("Pass the keyboard to {}", self.seats[next].name);
+			center_text(font, glyphs, &line, CENTER, 350.0, c, g);
+
+			let mut small = Text::new_color([0.0, 0.0, 0.0, 1.0], 20);
+			center_text(&mut small, glyphs, "[enter] Ready    [tab] Pass it on", CENTER, CENTER, c, g);
+			return;
+		}
+
+		let seat = &mut self.seats[self.active];
+		seat.canvas.render(c, g, device);
+		seat.game.lock().unwrap().render(font, glyphs, c, g, device);
+	}
+
+	/// Input goes to the seat at the keyboard, apart from the keys that pass it on, while
+	/// every seat's game keeps ticking.
+	pub fn process_event(&mut self, e: Event) {
+		match &e {
+			Event::Input(..) => {
+				let key = e.press_args().and_then(|button| match button {
+					Button::Keyboard(key) => Some(key),
+					_ => None,
+				});
+
+				match (self.passing_to, key) {
+					(Some(next), Some(Key::Return)) => {
+						self.active = next;
+						self.passing_to = None;
+					},
+
+					(Some(next), Some(Key::Tab)) => self.passing_to = Some((next + 1) % self.seats.len()),
+					(None, Some(Key::Tab)) => self.passing_to = Some((self.active + 1) % self.seats.len()),
+					(None, _) => self.seats[self.active].game.lock().unwrap().process_event(e),
+
+					// A button held down while the keyboard was passed on must not stay held.
+					(Some(_), _) if e.release_args().is_some() => {
+						for seat in self.seats.iter() {
+							seat.game.lock().unwrap().process_event(e.clone());
+						}
+					},
+
+					(Some(_), _) => {},
+				}
+			},
+
+			_ => {
+				for seat in self.seats.iter() {
+					seat.game.lock().unwrap().process_event(e.clone());
+				}
+
+				self.follow_drawer();
+			},
+		}
+	}
+
+	/// Passes the keyboard to whoever has just become the drawer, so they pick their word in
+	/// private.
+	fn follow_drawer(&mut self) {
+		let drawer = self.seats.iter().position(|seat| seat.game.lock().unwrap().is_drawing());
+		if drawer != self.drawer {
+			self.drawer = drawer;
+			if let Some(drawer) = drawer.filter(|drawer| *drawer != self.active) {
+				self.passing_to = Some(drawer);
+			}
+		}
+	}
+
+	/// Every seat has the same scoreboard, so it is saved once.
+	pub fn save_scores(&self) -> io::Result<()> {
+		self.seats[0].game.lock().unwrap().save_scores()
+	}
+}
@@ -0,0 +1,46 @@
+use piston_window::*;
+use crate::game::scoreboard::Scoreboard;
+use crate::game::text_util::{Glyphs, center_text};
+use crate::layout::{CENTER, clear_view};
+use crate::message::RoundResult;
+
+/// Rounds listed on the results screen; longer matches show their last rounds.
+const LISTED_ROUNDS: usize = 10;
+
+/// The end of a match: every round's word, who guessed it and how long it took, along with
+/// the final scores.
+pub struct MatchResults {
+	rounds: Vec<RoundResult>,
+	scoreboard: Scoreboard,
+}
+
+impl MatchResults {
+	pub fn new(rounds: Vec<RoundResult>, scoreboard: Scoreboard) -> Self {
+		MatchResults { rounds, scoreboard }
+	}
+
+	pub fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d) {
+		clear_view(c, g);
+
+		let headline = match self.scoreboard.leaders().as_slice() {
+			[] => "Match Over".to_owned(),
+			[leader] => format!("{} wins the match!", leader),
+			leaders => format!("Tied: {}", leaders.join(", ")),
+		};
+		center_text(font, glyphs, &headline, CENTER, 60.0, c, g);
+
+		let mut small = Text::new_color([0.0, 0.0, 0.0, 1.0], 20);
+		let first = self.rounds.len().saturating_sub(LISTED_ROUNDS);
+		for (i, round) in self.rounds.iter().enumerate().skip(first) {
+			let line = match &round.winner {
+				Some(winner) => format!("{}. '{}': {} in {}s", i + 1, round.word, winner, round.seconds),
+				None => format!("{}. '{}': not guessed", i + 1, round.word),
+			};
+
+			center_text(&mut small, glyphs, &line, CENTER, 110.0 + 28.0 * (i - first) as f64, c, g);
+		}
+
+		let listed = self.rounds.len() - first;
+		self.scoreboard.render(glyphs, 150.0 + 28.0 * listed as f64, c, g);
+	}
+}
@@ -3,65 +3,107 @@ extern crate image as im;
 
 mod communications;
 mod game_action;
-mod message;
+mod hotseat;
+mod match_results;
 mod text_util;
+mod palette;
 mod player;
+mod scoreboard;
+mod tools;
+
+pub use hotseat::Hotseat;
+pub use text_util::Glyphs;
 
 use std::sync::mpsc::{channel, Sender};
-use std::{env, thread};
+use std::time::Duration;
+use std::{io, thread};
+use std::io::Read;
+use std::path::Path;
+use std::error::Error;
 use std::sync::{Mutex, Arc};
-use std::net::{TcpStream, TcpListener};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
 use piston_window::*;
 use game_action::GameAction;
 use communications::Communications;
-use crate::canvas::CanvasOperation;
-use crate::game::message::{GameMessage, parse_game_message};
-use crate::game::text_util::Glyphs;
-use player::{Player, guesser::Guesser, drawer::Drawer, waiting_player::WaitingPlayer};
+use crate::canvas::{CANVAS_SIZES, CanvasOperation, Image};
+use crate::config::{Config, Role};
+use crate::layout::{CENTER, VIEW_SIZE};
+use crate::pictlog::{self, Recorder};
+use crate::message::{CAPABILITIES, Capabilities, GameMessage, PROTOCOL_VERSION, ProtocolError, Verdict, parse_game_message};
+use crate::message::{RECONNECT_ATTEMPTS, RECONNECT_DELAY_SECONDS};
+use crate::server::Server;
+use crate::transport::{BufferedTcpStream, LocalStream, Transport};
+use crate::game::text_util::{center_text, metrics};
+use match_results::MatchResults;
+use scoreboard::SCORES_FILE;
+use player::{Player, waiting_player::WaitingPlayer, disconnected::Disconnected};
+
+/// How many of the latest chat lines are shown, and how many characters of each.
+const CHAT_LINES: usize = 7;
+const CHAT_LINE_LEN: usize = 28;
+
+/// What the server agreed to in its hello.
+#[derive(Debug, Clone, Copy)]
+struct Terms {
+	capabilities: Capabilities,
+	canvas_size: u32,
+}
+
+/// Sits above the player's role: while the match is on the role handles everything, and once
+/// it is over the results replace it for good.
+enum Stage {
+	Playing,
+	Over(MatchResults),
+}
 
 pub struct Game {
+	stage: Stage,
+	address: String,
+	/// The attempt underway while the connection is being retried.
+	reconnecting: Option<u32>,
 	role: Box<dyn Player + Send>,
 	communications: Communications,
 }
 
 impl Game {
-	pub fn new(canvas_op_sender: Sender<CanvasOperation>) -> Arc<Mutex<Self>> {
-		let args: Vec<String> = env::args().collect();
-		if args.len() < 2 {
-			panic!("Not enough arguments provided (usage: address [is_host]")
-		}
+	pub fn new(config: Config, canvas_op_sender: Sender<CanvasOperation>) -> Arc<Mutex<Self>> {
+		let address = match &config.role {
+			Role::Host(address) | Role::Connect(address) | Role::Spectate(address) | Role::Replay(address, _) => address.clone(),
+			Role::Local(_) => String::new(),
+		};
 
-		let address = args[1].clone();
-		let hosting = args.len() > 2;
+		Game::start(address, canvas_op_sender, move |this, sender| Game::connect(config, this, sender))
+	}
+
+	/// One of the players sharing the window in a local game, talking to the in-process server
+	/// over `stream`.
+	pub fn local(name: String, stream: LocalStream, canvas_op_sender: Sender<CanvasOperation>) -> Arc<Mutex<Self>> {
+		Game::start("Local game".to_owned(), canvas_op_sender, move |this, sender| {
+			let (mut reader, writer) = stream.split();
+			let terms = Game::greet(&mut reader, &writer, &name, false)?;
+			this.lock().unwrap().communications.set_transport(Box::new(writer), terms.capabilities, terms.canvas_size);
+			Game::listen(&mut reader, this, sender)
+		})
+	}
 
+	/// Sets the game up and runs `connect` on a thread of its own. Returning normally means the
+	/// match is over and the results are showing; an error leaves the player disconnected.
+	fn start<F>(address: String, canvas_op_sender: Sender<CanvasOperation>, connect: F) -> Arc<Mutex<Self>>
+	where F: FnOnce(&Arc<Mutex<Self>>, &Sender<GameAction>) -> Result<(), Box<dyn Error>> + Send + 'static {
 		let (sender, receiver) = channel();
 		let this = Arc::new(Mutex::new(Game {
-			role: Box::new(WaitingPlayer::new(address.clone())),
+			stage: Stage::Playing,
+			address: address.clone(),
+			reconnecting: None,
+			role: Box::new(WaitingPlayer::new(address)),
 			communications: Communications::new(None, sender.clone(), canvas_op_sender),
 		}));
 
 		let connection_thread_ref = this.clone();
 		thread::spawn(move || {
-			let (stream, role): (TcpStream, Box<dyn Player + Send>) = if hosting {
-				(TcpListener::bind(address).unwrap().accept().unwrap().0, Box::new(Drawer::new()))
-			} else {
-				(TcpStream::connect(address).unwrap(), Box::new(Guesser::new()))
-			};
-			
-			let mut reader = stream.try_clone().unwrap();
-			
-			let mut this = connection_thread_ref.lock().unwrap();
-			this.communications.set_stream(stream);
-			this.communications.send_canvas_op(CanvasOperation::Clear);
-			this.role = role;
-			
-			let action_sender = sender.clone();
-			thread::spawn(move || {
-				loop {
-					let message = parse_game_message(&mut reader);
-					action_sender.send(Game::translate_message(message)).unwrap();
-				}
-			});
+			if let Err(e) = connect(&connection_thread_ref, &sender) {
+				sender.send(GameAction::Disconnected(e.to_string())).unwrap();
+			}
 		});
 		
 		let action_thread_ref = this.clone();
@@ -75,41 +117,296 @@ impl Game {
 		this
 	}
 
-	fn translate_message(message: GameMessage) -> GameAction {
-		match message {
-			GameMessage::Draw(x, y) => GameAction::Draw(x, y),
+	/// Connects to the server (starting one first when hosting) and forwards its messages as
+	/// actions until the match is over. A dropped connection is retried with backoff, under the
+	/// name the server knows the player by so that it hands back their place.
+	fn connect(config: Config, this: &Arc<Mutex<Self>>, sender: &Sender<GameAction>) -> Result<(), Box<dyn Error>> {
+		// A replay is only ever watched, whoever made the recording.
+		let spectating = matches!(config.role, Role::Spectate(_) | Role::Replay(..));
+		this.lock().unwrap().communications.set_spectating(spectating);
+
+		let address = match config.role {
+			Role::Host(address) => {
+				let server = Server::bind(address)?.configure(config.settings)?;
+				let mut local_addr = server.local_addr()?;
+				if local_addr.ip().is_unspecified() {
+					local_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), local_addr.port());
+				}
+
+				thread::spawn(move || server.run());
+				local_addr.to_string()
+			},
+
+			Role::Connect(address) | Role::Spectate(address) => address,
+			Role::Replay(file, speed) => pictlog::serve(Path::new(&file), speed)?.to_string(),
+			Role::Local(_) => return Err("A local game has no server to connect to".into()),
+		};
+
+		let mut name = config.name;
+		let (mut stream, mut terms) = Game::handshake(&address, &name, spectating)?;
+		if let Some(path) = &config.record {
+			let recorder = Recorder::create(Path::new(path), terms.canvas_size).map_err(|e| format!("Could not record to '{}': {}", path, e))?;
+			this.lock().unwrap().communications.set_recorder(recorder);
+		}
+
+		loop {
+			let mut reader = stream.try_clone()?;
+			let transport = BufferedTcpStream::new(stream)?;
+			this.lock().unwrap().communications.set_transport(Box::new(transport), terms.capabilities, terms.canvas_size);
+
+			let lost = match Game::listen(&mut reader, this, sender) {
+				Ok(()) => return Ok(()),
+				Err(e) => e,
+			};
+
+			if let Some(local_name) = this.lock().unwrap().communications.local_name() {
+				name = local_name;
+			}
+
+			(stream, terms) = Game::reconnect(&address, &name, spectating, sender).ok_or(lost)?;
+			sender.send(GameAction::Reconnected).unwrap();
+		}
+	}
+
+	/// Opens a connection and exchanges hellos, returning what the server agreed to.
+	fn handshake(address: &str, name: &str, spectating: bool) -> Result<(TcpStream, Terms), Box<dyn Error>> {
+		let stream = TcpStream::connect(address)?;
+		let terms = Game::greet(&mut stream.try_clone()?, &stream, name, spectating)?;
+		Ok((stream, terms))
+	}
+
+	/// Exchanges hellos over an open connection, returning the capabilities both sides support
+	/// and the canvas size the server picked.
+	fn greet<R: Read>(reader: &mut R, transport: &dyn Transport, name: &str, spectating: bool) -> Result<Terms, Box<dyn Error>> {
+		transport.send(&GameMessage::Hello {
+			protocol_version: PROTOCOL_VERSION,
+			client_name: name.to_owned(),
+			capabilities: CAPABILITIES,
+			canvas_size: 0,
+			spectating,
+		})?;
+
+		match parse_game_message(reader)? {
+			GameMessage::Hello { protocol_version, capabilities, canvas_size, .. } if protocol_version == PROTOCOL_VERSION => {
+				if !CANVAS_SIZES.contains(&canvas_size) {
+					return Err(format!("Server picked an unsupported canvas size of {}", canvas_size).into());
+				}
+
+				Ok(Terms {
+					capabilities: capabilities & CAPABILITIES,
+					canvas_size,
+				})
+			},
+
+			GameMessage::Hello { protocol_version, .. } => {
+				Err(format!("Server speaks protocol version {}, client speaks {}", protocol_version, PROTOCOL_VERSION).into())
+			},
+
+			GameMessage::Reject(reason) => Err(reason.into()),
+			_ => Err("Server did not send a hello".into()),
+		}
+	}
+
+	/// Tries to connect again, waiting twice as long after each failed attempt.
+	fn reconnect(address: &str, name: &str, spectating: bool, sender: &Sender<GameAction>) -> Option<(TcpStream, Terms)> {
+		let mut delay = Duration::from_secs(RECONNECT_DELAY_SECONDS as u64);
+		for attempt in 1..=RECONNECT_ATTEMPTS {
+			sender.send(GameAction::Reconnecting(attempt)).unwrap();
+			thread::sleep(delay);
+			delay *= 2;
+
+			if let Ok(connection) = Game::handshake(address, name, spectating) {
+				return Some(connection);
+			}
+		}
+
+		None
+	}
+
+	/// Forwards the server's messages as actions. Returns once the match is over, or with an
+	/// error when the connection is lost.
+	fn listen<R: Read>(reader: &mut R, this: &Arc<Mutex<Self>>, sender: &Sender<GameAction>) -> Result<(), Box<dyn Error>> {
+		loop {
+			match parse_game_message(reader) {
+				Ok(message) => {
+					{
+						// Strokes off the edge of the canvas are dropped rather than drawn.
+						let mut game = this.lock().unwrap();
+						if !message.in_bounds(game.communications.canvas_size()) {
+							continue;
+						}
+
+						game.communications.record(&message);
+					}
+
+					let over = matches!(message, GameMessage::MatchOver(_));
+					if let Some(action) = Game::translate_message(message) {
+						sender.send(action).unwrap();
+					}
+
+					if over {
+						return Ok(());
+					}
+				},
+
+				Err(ProtocolError::Eof) => return Err("Connection closed".into()),
+				Err(e) => return Err(e.into()),
+			}
+		}
+	}
+
+	fn translate_message(message: GameMessage) -> Option<GameAction> {
+		Some(match message {
+			GameMessage::Draw(x, y, color, brush) => GameAction::Draw(x, y, color, brush),
 			GameMessage::SetTimeRemaining(t) => GameAction::SetTimeRemaining(t),
 			GameMessage::SetWordSkeleton(skeleton) => GameAction::SetWordSkeleton(skeleton),
-			GameMessage::Guess(guess) => GameAction::Guess(guess),
-			GameMessage::GuessResult(res) => GameAction::GuessResult(res),
+			GameMessage::Guess(id, guess) => GameAction::Guess(id, guess),
+			GameMessage::GuessResult(id, res) => GameAction::GuessResult(id, res),
 			GameMessage::GameOver(word) => GameAction::GameOver(word),
-			GameMessage::SwapRoles => GameAction::SwapRoles,
-			GameMessage::Erase(x, y) => GameAction::Erase(x, y),
-			GameMessage::DrawLine(x1, y1, x2, y2) => GameAction::DrawLine(x1, y1, x2, y2),
-			GameMessage::EraseLine(x1, y1, x2, y2) => GameAction::EraseLine(x1, y1, x2, y2),
-		}
+			GameMessage::SwapRoles(drawer_id) => GameAction::SwapRoles(drawer_id),
+			GameMessage::Erase(x, y, brush) => GameAction::Erase(x, y, brush),
+			GameMessage::DrawLine(x1, y1, x2, y2, color, brush) => GameAction::DrawLine(x1, y1, x2, y2, color, brush),
+			GameMessage::EraseLine(x1, y1, x2, y2, brush) => GameAction::EraseLine(x1, y1, x2, y2, brush),
+			GameMessage::Fill(x, y, color) => GameAction::Fill(x, y, color),
+			GameMessage::Undo => GameAction::Undo,
+			GameMessage::Redo => GameAction::Redo,
+			GameMessage::Leave(id) => GameAction::PlayerLeft(id),
+			GameMessage::Roster(local_id, roster) => GameAction::Roster(local_id, roster),
+			GameMessage::WordChoices(words) => GameAction::WordChoices(words),
+			GameMessage::ScoreUpdate(scores) => GameAction::ScoreUpdate(scores),
+			GameMessage::Settings { round_seconds, choices, rounds } => GameAction::Settings(round_seconds, choices, rounds),
+			GameMessage::MatchOver(rounds) => GameAction::MatchOver(rounds),
+			GameMessage::CanvasSnapshot(width, height, pixels) => GameAction::CanvasSnapshot(width, height, pixels),
+			GameMessage::Answer(word) => GameAction::Answer(word),
+			GameMessage::Polyline { color, brush, points } => GameAction::Polyline(color, brush, points),
+			GameMessage::ErasePolyline { brush, points } => GameAction::ErasePolyline(brush, points),
+			GameMessage::Hello { .. } | GameMessage::Reject(_) |
+			GameMessage::PickWord(_) | GameMessage::NextRound => return None,
+		})
 	}
 
 	pub fn process_action(&mut self, action: GameAction) {
-		match action {
-			GameAction::Draw(x, y) => {
-				self.communications.send_canvas_op(CanvasOperation::Pixel(x, y, 0, 0, 255));
+		if let Stage::Over(_) = self.stage {
+			return;
+		}
+
+		match &action {
+			GameAction::Draw(x, y, color, brush) => {
+				self.communications.send_canvas_op(CanvasOperation::Pixel(*x, *y, *color, *brush));
+			},
+
+			GameAction::Erase(x, y, brush) => {
+				self.communications.send_canvas_op(CanvasOperation::Erase(*x, *y, *brush));
+			},
+
+			GameAction::SwapRoles(drawer_id) => {
+				self.communications.set_drawer_id(Some(*drawer_id));
+				self.communications.send_canvas_op(CanvasOperation::Clear);
+			},
+
+			GameAction::DrawLine(x1, y1, x2, y2, color, brush) => {
+				self.communications.send_canvas_op(CanvasOperation::Line(*x1, *y1, *x2, *y2, *color, *brush));
+			},
+
+			GameAction::EraseLine(x1, y1, x2, y2, brush) => {
+				self.communications.send_canvas_op(CanvasOperation::EraseLine(*x1, *y1, *x2, *y2, *brush));
+			},
+
+			GameAction::Polyline(color, brush, points) => {
+				for line in points.windows(2) {
+					let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+					self.communications.send_canvas_op(CanvasOperation::Line(x1 as u32, y1 as u32, x2 as u32, y2 as u32, *color, *brush));
+				}
+			},
+
+			GameAction::ErasePolyline(brush, points) => {
+				for line in points.windows(2) {
+					let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+					self.communications.send_canvas_op(CanvasOperation::EraseLine(x1 as u32, y1 as u32, x2 as u32, y2 as u32, *brush));
+				}
+			},
+
+			// The drag is over, so the rest of its stroke need not wait.
+			GameAction::MouseRelease => {
+				self.communications.flush();
+			},
+
+			GameAction::Fill(x, y, color) => {
+				self.communications.send_canvas_op(CanvasOperation::Fill(*x, *y, *color));
+			},
+
+			GameAction::Undo => {
+				self.communications.send_canvas_op(CanvasOperation::Undo);
+			},
+
+			GameAction::Redo => {
+				self.communications.send_canvas_op(CanvasOperation::Redo);
+			},
+
+			GameAction::CanvasSnapshot(width, height, pixels) => {
+				if let Some(image) = Image::from_raw(*width, *height, pixels.clone()) {
+					self.communications.send_canvas_op(CanvasOperation::Restore(image));
+				}
+			},
+
+			GameAction::Guess(id, guess) => {
+				let line = format!("{}: {}", self.communications.player_name(*id), guess);
+				self.communications.push_chat(line);
+			},
+
+			GameAction::GuessResult(id, Verdict::Close) => {
+				let line = format!("{} is close!", self.communications.player_name(*id));
+				self.communications.push_chat(line);
+			},
+
+			GameAction::GuessResult(id, Verdict::Correct(_)) => {
+				let line = format!("{} guessed the word!", self.communications.player_name(*id));
+				self.communications.push_chat(line);
 			},
 
-			GameAction::Erase(x, y) => {
-				self.communications.send_canvas_op(CanvasOperation::Erase(x, y));
+			GameAction::GameOver(word) => {
+				self.communications.push_chat(format!("Time's up! The word was '{}'", word));
 			},
 
-			GameAction::SwapRoles => {
+			GameAction::PlayerLeft(id) => {
+				self.communications.remove_player(*id);
+			},
+
+			GameAction::Roster(local_id, roster) => {
+				self.communications.set_roster(*local_id, roster.clone());
+			},
+
+			GameAction::ScoreUpdate(scores) => {
+				self.communications.set_scores(scores.clone());
+			},
+
+			GameAction::Settings(round_seconds, choices, rounds) => {
+				self.communications.set_settings(*round_seconds, *choices, *rounds);
+			},
+
+			GameAction::Reconnecting(attempt) => {
+				self.reconnecting = Some(*attempt);
+			},
+
+			// The server resends the round, so start over from a blank canvas.
+			GameAction::Reconnected => {
+				self.reconnecting = None;
+				self.communications.set_drawer_id(None);
 				self.communications.send_canvas_op(CanvasOperation::Clear);
+				self.role = Box::new(WaitingPlayer::new(self.address.clone()));
+				return;
 			},
 
-			GameAction::DrawLine(x1, y1, x2, y2) => {
-				self.communications.send_canvas_op(CanvasOperation::Line(x1, y1, x2, y2, 0, 0, 255));
+			GameAction::Disconnected(reason) => {
+				self.reconnecting = None;
+				self.communications.set_drawer_id(None);
+				self.role = Box::new(Disconnected::new(reason.clone()));
 			},
 
-			GameAction::EraseLine(x1, y1, x2, y2) => {
-				self.communications.send_canvas_op(CanvasOperation::EraseLine(x1, y1, x2, y2));
+			GameAction::MatchOver(rounds) => {
+				self.communications.set_drawer_id(None);
+				self.stage = Stage::Over(MatchResults::new(rounds.clone(), self.communications.scoreboard()));
+				return;
 			},
 
 			_ => {}
@@ -124,7 +421,76 @@ impl Game {
 		self.communications.process_event(e)
 	}
 
+	/// Whether the local player is the drawer in the match underway.
+	pub fn is_drawing(&self) -> bool {
+		let local_id = self.communications.local_id();
+		matches!(self.stage, Stage::Playing) && local_id != 0 && self.communications.drawer_id() == Some(local_id)
+	}
+
+	/// Appends the session's final scoreboard to the local scores file.
+	pub fn save_scores(&self) -> io::Result<()> {
+		let scoreboard = self.communications.scoreboard();
+		if scoreboard.is_empty() {
+			return Ok(());
+		}
+
+		scoreboard.append_to(Path::new(SCORES_FILE))
+	}
+
 	pub fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, device: &mut gfx_device_gl::Device) {
+		if let Stage::Over(results) = &self.stage {
+			results.render(font, glyphs, c, g);
+			return;
+		}
+
 		self.role.render(font, glyphs, c, g, device);
+		self.render_roster(glyphs, c, g);
+		self.render_chat(glyphs, c, g);
+
+		if let Some(attempt) = self.reconnecting {
+			rectangle([1.0, 1.0, 1.0, 0.8], [0.0, 0.0, VIEW_SIZE, VIEW_SIZE], c.transform, g);
+			center_text(font, glyphs, "Reconnecting...", CENTER, 350.0, c, g);
+			let mut small = Text::new_color([0.0, 0.0, 0.0, 1.0], 20);
+			let line = format!("Attempt {} of {}", attempt, RECONNECT_ATTEMPTS);
+			center_text(&mut small, glyphs, &line, CENTER, CENTER, c, g);
+		}
+	}
+
+	/// The latest guesses, oldest at the top, in the bottom right corner.
+	fn render_chat(&self, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d) {
+		let font = Text::new_color([0.2, 0.2, 0.2, 1.0], 16);
+		let chat = self.communications.chat();
+		let visible = &chat[chat.len().saturating_sub(CHAT_LINES)..];
+
+		for (i, line) in visible.iter().enumerate() {
+			let line: String = line.chars().take(CHAT_LINE_LEN).collect();
+			font.draw(
+				&line,
+				glyphs,
+				&c.draw_state,
+				c.transform.trans(540.0, 580.0 + 20.0 * (i as f64)), g
+			).unwrap();
+		}
+	}
+
+	fn render_roster(&self, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d) {
+		let font = Text::new_color([0.0, 0.0, 0.0, 1.0], 16);
+		let drawer_id = self.communications.drawer_id();
+
+		for (i, (id, name)) in self.communications.roster().iter().enumerate() {
+			let line = if Some(*id) == drawer_id {
+				format!("{} (drawing)", name)
+			} else {
+				name.clone()
+			};
+
+			let w = metrics(&font, &line, glyphs);
+			font.draw(
+				&line,
+				glyphs,
+				&c.draw_state,
+				c.transform.trans(790.0 - w, 20.0 + 20.0 * (i as f64)), g
+			).unwrap();
+		}
 	}
 }
\ No newline at end of file
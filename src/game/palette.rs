@@ -0,0 +1,60 @@
+use piston_window::*;
+use crate::canvas::Color;
+use crate::layout::VIEW_SIZE;
+
+/// Colors the drawer picks from, bound to the number keys in order (1-9, then 0).
+pub const PALETTE: &[Color] = &[
+	Color::new(0, 0, 0),
+	Color::new(220, 30, 30),
+	Color::new(245, 140, 20),
+	Color::new(250, 215, 30),
+	Color::new(40, 170, 50),
+	Color::new(0, 0, 255),
+	Color::new(130, 50, 180),
+	Color::new(120, 75, 35),
+	Color::new(250, 140, 190),
+	Color::new(140, 140, 140),
+];
+
+pub const DEFAULT_COLOR: usize = 5;
+
+// Swatch layout in hundredths of the view, a row along the bottom edge.
+const GRID: u32 = 100;
+const SWATCH_X: u32 = 2;
+const SWATCH_Y: u32 = 93;
+const SWATCH_SIZE: u32 = 5;
+const SWATCH_SPACING: u32 = 6;
+
+/// The palette entry bound to a number key.
+pub fn swatch_for_number(n: u8) -> Option<usize> {
+	let index = (n as usize + 9) % 10;
+	if index < PALETTE.len() { Some(index) } else { None }
+}
+
+/// The palette entry whose swatch covers the pixel of a `canvas_size` canvas, if any.
+pub fn swatch_at(x: u32, y: u32, canvas_size: u32) -> Option<usize> {
+	let (x, y) = (x * GRID / canvas_size, y * GRID / canvas_size);
+	if x < SWATCH_X || !(SWATCH_Y..SWATCH_Y + SWATCH_SIZE).contains(&y) {
+		return None;
+	}
+
+	let index = ((x - SWATCH_X) / SWATCH_SPACING) as usize;
+	let within = (x - SWATCH_X) % SWATCH_SPACING < SWATCH_SIZE;
+	if within && index < PALETTE.len() { Some(index) } else { None }
+}
+
+pub fn render(selected: usize, c: Context, g: &mut G2d) {
+	for (i, color) in PALETTE.iter().enumerate() {
+		let pixel = VIEW_SIZE / GRID as f64;
+		let x = (SWATCH_X + SWATCH_SPACING * i as u32) as f64 * pixel;
+		let y = SWATCH_Y as f64 * pixel;
+		let size = SWATCH_SIZE as f64 * pixel;
+
+		if i == selected {
+			rectangle([0.0, 0.0, 0.0, 1.0], [x - 4.0, y - 4.0, size + 8.0, size + 8.0], c.transform, g);
+		}
+
+		let fill = [color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0, 1.0];
+		rectangle(fill, [x, y, size, size], c.transform, g);
+	}
+}
@@ -0,0 +1,30 @@
+use piston_window::*;
+use crate::game::game_action::GameAction;
+use crate::game::communications::Communications;
+use crate::game::text_util::{Glyphs, *};
+use crate::layout::CENTER;
+use crate::game::player::Player;
+
+/// Shown once the connection to the server is gone; there is nothing left to do but quit.
+pub struct Disconnected {
+	reason: String,
+}
+
+impl Disconnected {
+	pub fn new(reason: String) -> Self {
+		Self {
+			reason,
+		}
+	}
+}
+
+impl Player for Disconnected {
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
+		center_text(font, glyphs, "Peer Disconnected", CENTER, 150.0, c, g);
+		center_text(font, glyphs, &self.reason, CENTER, 250.0, c, g);
+	}
+
+	fn process_action(&self, _communications: &mut Communications, _action: GameAction) -> Option<Box<dyn Player + Send>> {
+		None
+	}
+}
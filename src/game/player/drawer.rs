@@ -1,46 +1,39 @@
-use std::cmp;
-use rand::seq::SliceRandom;
 use piston_window::*;
+use crate::canvas::CanvasOperation;
 use crate::game::game_action::GameAction;
 use crate::game::communications::Communications;
-use crate::game::message::GameMessage;
+use crate::message::{GameMessage, Verdict, capability};
 use crate::game::text_util::{Glyphs, *};
-use crate::game::player::{Player, DRAWING_TIME, guesser::Guesser};
+use crate::layout::CENTER;
+use crate::game::player::{Player, role_for};
+use crate::game::scoreboard::Scoreboard;
+use crate::game::tools::Tools;
 
 pub enum Drawer {
 	PickingWord(Vec<String>),
-	Drawing(String, f64, u32),
-	Done(bool),
+	Drawing(String, u32, Tools),
+	Done(bool, Scoreboard),
 }
 
-const WORDS: &'static [&'static str] = &[
-	"bike", "snowman", "tree", "flower", "basketball",
-	"mountain", "turtle", "book", 
-];
-
 impl Drawer {
 	pub fn new() -> Self {
-		let words: Vec<String> = WORDS
-			.choose_multiple(&mut rand::thread_rng(), 3)
-			.map(|word| String::from(*word))
-			.collect();
-
-		Drawer::PickingWord(words)
+		Drawer::PickingWord(Vec::new())
 	}
 }
 
 impl Player for Drawer {
-	fn render(self: &Self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
 		match self {
 			Drawer::PickingWord(words) => {
-				center_text(font, glyphs, "Pick Word", 400.0, 50.0, c, g);
-				for i in 0..words.len() {
-					center_text(font, glyphs, &format!("[{}] {}", i + 1, words[i]), 400.0, 100.0 + 50.0 * (i as f64), c, g);
+				center_text(font, glyphs, "Pick Word", CENTER, 50.0, c, g);
+				for (i, word) in words.iter().enumerate() {
+					center_text(font, glyphs, &format!("[{}] {}", i + 1, word), CENTER, 100.0 + 50.0 * (i as f64), c, g);
 				}
 			},
 
-			Drawer::Drawing(word, _dt, time) => {
-				center_text(font, glyphs, &format!("Drawing '{}'", word), 400.0, 50.0, c, g);
+			Drawer::Drawing(word, time, tools) => {
+				center_text(font, glyphs, &format!("Drawing '{}'", word), CENTER, 50.0, c, g);
+				tools.render(glyphs, c, g);
 				
 				font.draw(
 					&time.to_string(),
@@ -50,39 +43,41 @@ impl Player for Drawer {
 				).unwrap();
 			},
 
-			Drawer::Done(won) => {
+			Drawer::Done(won, scoreboard) => {
 				if *won {
-					center_text(font, glyphs, "You Win", 400.0, 150.0, c, g);
+					center_text(font, glyphs, "You Win", CENTER, 150.0, c, g);
 				} else {
-					center_text(font, glyphs, "Time's Up", 400.0, 150.0, c, g);
+					center_text(font, glyphs, "Time's Up", CENTER, 150.0, c, g);
 				}
 
-				center_text(font, glyphs, "[y] Play Again?", 400.0, 250.0, c, g);
+				center_text(font, glyphs, "[y] Next Round", CENTER, 250.0, c, g);
+				scoreboard.render(glyphs, 320.0, c, g);
 			}
 		}
 	}
 
-	fn process_action(self: &Self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> {
-		if let GameAction::SwapRoles = action {
-			return Some(Box::new(Guesser::new()))
+	fn process_action(&self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> {
+		if let GameAction::SwapRoles(drawer_id) = action {
+			return Some(role_for(communications, drawer_id))
 		}
 
 		match self {
 			Drawer::PickingWord(words) => {
 				match action {
+					GameAction::WordChoices(words) => {
+						Some(Box::new(Drawer::PickingWord(words)))
+					},
+
 					GameAction::TypeNumber(n) => {
 						let n = n as usize;
 						if n > 0 && n <= words.len() {
-							let word = words[n - 1].clone();
-							let skeleton: String = word.chars().map(|c| {
-								match c {
-									' ' => ' ',
-									_ => '_'
-								}
-							}).collect();
-							communications.send_message(GameMessage::SetWordSkeleton(skeleton));
-
-							Some(Box::new(Drawer::Drawing(word, 0.0, DRAWING_TIME)))
+							communications.send_message(GameMessage::PickWord((n - 1) as u32));
+							// The server never tells the drawer the word, so a replay of their
+							// recording would have no other way to show it.
+							communications.record(&GameMessage::Answer(words[n - 1].clone()));
+
+							let tools = Tools::new(communications.supports(capability::FILL), communications.canvas_size());
+							Some(Box::new(Drawer::Drawing(words[n - 1].clone(), communications.settings().round_seconds, tools)))
 						} else {
 							None
 						}
@@ -92,74 +87,85 @@ impl Player for Drawer {
 				}
 			},
 
-			Drawer::Drawing(word, cdt, time) => {
+			Drawer::Drawing(word, time, tools) => {
+				if let Some(tools) = tools.process_action(&action) {
+					return Some(Box::new(Drawer::Drawing(word.clone(), *time, tools)));
+				}
+
+				let (color, brush) = (tools.color(), tools.brush());
 				match action {
+					GameAction::LeftClick(x, y) if tools.filling() => {
+						communications.send_message(GameMessage::Fill(x, y, color));
+						communications.send_action(GameAction::Fill(x, y, color));
+						None
+					},
+
 					GameAction::LeftClick(x, y) => {
-						communications.send_message(GameMessage::Draw(x, y));
-						communications.send_action(GameAction::Draw(x, y));
+						communications.send_message(GameMessage::Draw(x, y, color, brush));
+						communications.send_action(GameAction::Draw(x, y, color, brush));
 						None
 					},
 
 					GameAction::RightClick(x, y) => {
-						communications.send_message(GameMessage::Erase(x, y));
-						communications.send_action(GameAction::Erase(x, y));
+						communications.send_message(GameMessage::Erase(x, y, brush));
+						communications.send_action(GameAction::Erase(x, y, brush));
 						None
 					},
 
 					GameAction::LeftClickDrag(x1, y1, x2, y2) => {
-						communications.send_message(GameMessage::DrawLine(x1, y1, x2, y2));
-						communications.send_action(GameAction::DrawLine(x1, y1, x2, y2));
+						if !tools.filling() && !tools.picking() {
+							communications.send_line(x1, y1, x2, y2, color, brush);
+							communications.send_action(GameAction::DrawLine(x1, y1, x2, y2, color, brush));
+						}
+
 						None
 					},
 		
 					GameAction::RightClickDrag(x1, y1, x2, y2) => {
-						communications.send_message(GameMessage::EraseLine(x1, y1, x2, y2));
-						communications.send_action(GameAction::EraseLine(x1, y1, x2, y2));
+						communications.send_erase_line(x1, y1, x2, y2, brush);
+						communications.send_action(GameAction::EraseLine(x1, y1, x2, y2, brush));
 						None
 					},
 
-					GameAction::Update(dt) => {
-						let cdt = cdt + dt;
-						if cdt > 1.0 {
-							communications.send_action(GameAction::Tick);
-							None
-						} else {
-							Some(Box::new(Drawer::Drawing(word.clone(), cdt, *time)))
-						}
+					GameAction::UndoShortcut if communications.supports(capability::UNDO) => {
+						communications.send_message(GameMessage::Undo);
+						communications.send_action(GameAction::Undo);
+						None
 					},
 
-					GameAction::Tick => {
-						let time = cmp::max(time - 1, 0);
-						communications.send_message(GameMessage::SetTimeRemaining(time));
-						
-						if time > 0 {
-							Some(Box::new(Drawer::Drawing(word.clone(), 0.0, time)))
-						} else {
-							communications.send_message(GameMessage::GameOver(word.clone()));
-							Some(Box::new(Drawer::Done(false)))
-						}
+					GameAction::RedoShortcut if communications.supports(capability::UNDO) => {
+						communications.send_message(GameMessage::Redo);
+						communications.send_action(GameAction::Redo);
+						None
 					},
 
-					GameAction::Guess(guess) => {
-						if guess == *word {
-							communications.send_message(GameMessage::GuessResult(Some(word.clone())));
-							Some(Box::new(Drawer::Done(true)))
-						} else {
-							communications.send_message(GameMessage::GuessResult(None));
-							None
-						}
+					GameAction::SetTimeRemaining(time) => {
+						Some(Box::new(Drawer::Drawing(word.clone(), time, *tools)))
+					},
+
+					GameAction::GameOver(_) => {
+						communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+						Some(Box::new(Drawer::Done(false, communications.scoreboard())))
+					},
+
+					GameAction::GuessResult(_, Verdict::Correct(_)) => {
+						communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+						Some(Box::new(Drawer::Done(true, communications.scoreboard())))
 					},
 
 					_ => None
 				}
 			},
 
-			Drawer::Done(_) => {
+			Drawer::Done(won, _) => {
 				match action {
+					GameAction::ScoreUpdate(_) => {
+						Some(Box::new(Drawer::Done(*won, communications.scoreboard())))
+					},
+
 					GameAction::TypeLetter(char) => {
 						if char == 'y' {
-							communications.send_action(GameAction::SwapRoles);
-							communications.send_message(GameMessage::SwapRoles);
+							communications.send_message(GameMessage::NextRound);
 						}
 
 						None
@@ -171,4 +177,4 @@ impl Player for Drawer {
 		}
 		
 	}
-}
\ No newline at end of file
+}
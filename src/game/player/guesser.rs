@@ -1,14 +1,17 @@
 use piston_window::*;
+use crate::canvas::CanvasOperation;
 use crate::game::game_action::GameAction;
 use crate::game::communications::Communications;
-use crate::game::message::GameMessage;
+use crate::message::{GameMessage, Verdict};
 use crate::game::text_util::{Glyphs, *};
-use crate::game::player::{Player, drawer::Drawer, DRAWING_TIME};
+use crate::layout::CENTER;
+use crate::game::player::{Player, role_for};
+use crate::game::scoreboard::Scoreboard;
 
 pub enum Guesser {
 	WaitingForDrawer,
 	Guessing(u32, String, String),
-	Done(bool, String),
+	Done(bool, String, Scoreboard),
 }
 
 impl Guesser {
@@ -17,23 +20,34 @@ impl Guesser {
 	}
 }
 
+/// Letters the server revealed as hints, drawn in a different color from the blanks.
+const REVEALED_COLOR: [f32; 4] = [0.1, 0.4, 0.8, 1.0];
+
+/// Longest guess that can be typed.
+const MAX_GUESS_LEN: usize = 32;
+
+pub fn render_skeleton(font: &mut Text, glyphs: &mut Glyphs<'_>, skeleton: &str, c: Context, g: &mut G2d) {
+	let revealed_font = Text::new_color(REVEALED_COLOR, font.font_size);
+	let mut x = CENTER - metrics(font, skeleton, glyphs) * 0.5;
+
+	for letter in skeleton.chars() {
+		let font = if letter == '_' || letter == ' ' { &*font } else { &revealed_font };
+		let letter = letter.to_string();
+		font.draw(&letter, glyphs, &c.draw_state, c.transform.trans(x, 50.0), g).unwrap();
+		x += metrics(font, &letter, glyphs);
+	}
+}
+
 impl Player for Guesser {
-	fn render(self: &Self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
 		match self {
 			Guesser::WaitingForDrawer => {
-				center_text(font, glyphs, "Waiting for Drawer", 400.0, 50.0, c, g);
+				center_text(font, glyphs, "Waiting for Drawer", CENTER, 50.0, c, g);
 			},
 
 			Guesser::Guessing(time, skeleton, guess) => {
-				let guess: String = skeleton.chars().enumerate().map(|(index, skel_char)| {
-					if let Some(guess_char) = guess.chars().nth(index) {
-						guess_char
-					} else {
-						skel_char
-					}
-				}).collect();
-
-				center_text(font, glyphs, &guess, 400.0, 50.0, c, g);
+				render_skeleton(font, glyphs, skeleton, c, g);
+				center_text(font, glyphs, &format!("> {}_", guess), CENTER, 100.0, c, g);
 
 				font.draw(
 					&time.to_string(),
@@ -43,28 +57,29 @@ impl Player for Guesser {
 				).unwrap();
 			},
 
-			Guesser::Done(did_win, word) => {
+			Guesser::Done(did_win, word, scoreboard) => {
 				if *did_win {
-					center_text(font, glyphs, "You Win", 400.0, 150.0, c, g);
+					center_text(font, glyphs, "You Win", CENTER, 150.0, c, g);
 				} else {
-					center_text(font, glyphs, "Time's Up", 400.0, 150.0, c, g);
+					center_text(font, glyphs, "Time's Up", CENTER, 150.0, c, g);
 				}
 
-				center_text(font, glyphs, &format!("'{}'", word), 400.0, 250.0, c, g);
+				center_text(font, glyphs, &format!("'{}'", word), CENTER, 250.0, c, g);
+				scoreboard.render(glyphs, 320.0, c, g);
 			}
 		};
 	}
 
-	fn process_action(self: &Self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> {
-		if let GameAction::SwapRoles = action {
-			return Some(Box::new(Drawer::new()))
+	fn process_action(&self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> {
+		if let GameAction::SwapRoles(drawer_id) = action {
+			return Some(role_for(communications, drawer_id))
 		}
 
 		match self {
 			Guesser::WaitingForDrawer => {
 				match action {
 					GameAction::SetWordSkeleton(skeleton) => {
-						Some(Box::new(Guesser::Guessing(DRAWING_TIME, skeleton, "".to_owned())))
+						Some(Box::new(Guesser::Guessing(communications.settings().round_seconds, skeleton, "".to_owned())))
 					}
 
 					_ => None
@@ -78,11 +93,16 @@ impl Player for Guesser {
 					},
 
 					GameAction::GameOver(word) => {
-						Some(Box::new(Guesser::Done(false, word.clone())))
+						communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+						Some(Box::new(Guesser::Done(false, word, communications.scoreboard())))
+					},
+
+					GameAction::SetWordSkeleton(skeleton) => {
+						Some(Box::new(Guesser::Guessing(*t, skeleton, guess.clone())))
 					},
 
-					GameAction::TypeLetter(char) => {
-						if guess.len() < skeleton.len() {
+					GameAction::TypeLetter(char) | GameAction::TypeSymbol(char @ ' ') => {
+						if guess.len() < MAX_GUESS_LEN {
 							let mut new_guess = guess.clone();
 							new_guess.push(char);
 							
@@ -93,7 +113,7 @@ impl Player for Guesser {
 					},
 
 					GameAction::DeleteLetter => {
-						if guess.len() > 0 {
+						if !guess.is_empty() {
 							let mut new_guess = guess.clone();
 							new_guess.pop();
 							
@@ -104,18 +124,26 @@ impl Player for Guesser {
 					},
 
 					GameAction::Enter => {
-						if guess.len() == skeleton.len() {
-							communications.send_message(GameMessage::Guess(guess.clone()));
+						if !guess.trim().is_empty() {
+							communications.send_message(GameMessage::Guess(communications.local_id(), guess.clone()));
 						}
 
 						None
 					},
 
-					GameAction::GuessResult(res) => {
-						if let Some(word) = res {
-							Some(Box::new(Guesser::Done(true, word.clone())))
-						} else {
-							Some(Box::new(Guesser::Guessing(*t, skeleton.clone(), "".to_owned())))
+					GameAction::GuessResult(id, verdict) => {
+						let own_guess = id == communications.local_id();
+						match verdict {
+							Verdict::Correct(word) => {
+								communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+								Some(Box::new(Guesser::Done(own_guess, word, communications.scoreboard())))
+							},
+
+							Verdict::Wrong | Verdict::Close if own_guess => {
+								Some(Box::new(Guesser::Guessing(*t, skeleton.clone(), "".to_owned())))
+							},
+
+							_ => None
 						}
 					},
 
@@ -123,7 +151,15 @@ impl Player for Guesser {
 				}
 			},
 
-			_ => None
+			Guesser::Done(did_win, word, _) => {
+				match action {
+					GameAction::ScoreUpdate(_) => {
+						Some(Box::new(Guesser::Done(*did_win, word.clone(), communications.scoreboard())))
+					},
+
+					_ => None
+				}
+			}
 		}
 	}
 }
@@ -2,16 +2,30 @@
 pub mod guesser;
 pub mod drawer;
 pub mod waiting_player;
+pub mod disconnected;
+pub mod spectator;
 
 use piston_window::*;
 use crate::game::game_action::GameAction;
 use crate::game::communications::Communications;
 use crate::game::text_util::Glyphs;
-
-const DRAWING_TIME: u32 = 100;
+use drawer::Drawer;
+use guesser::Guesser;
+use spectator::Spectator;
 
 pub trait Player {
-	fn render(self: &Self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, device: &mut gfx_device_gl::Device);
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, device: &mut gfx_device_gl::Device);
+
+	fn process_action(&self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>>;
+}
 
-	fn process_action(self: &Self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>>;
+/// The role the local player takes in a round drawn by `drawer_id`.
+pub fn role_for(communications: &Communications, drawer_id: u32) -> Box<dyn Player + Send> {
+	if communications.spectating() {
+		Box::new(Spectator::new())
+	} else if drawer_id == communications.local_id() {
+		Box::new(Drawer::new())
+	} else {
+		Box::new(Guesser::new())
+	}
 }
\ No newline at end of file
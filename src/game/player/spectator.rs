@@ -0,0 +1,124 @@
+use piston_window::*;
+use crate::canvas::CanvasOperation;
+use crate::game::game_action::GameAction;
+use crate::game::communications::Communications;
+use crate::message::Verdict;
+use crate::game::text_util::{Glyphs, *};
+use crate::layout::CENTER;
+use crate::game::player::{Player, role_for};
+use crate::game::player::guesser::render_skeleton;
+use crate::game::scoreboard::Scoreboard;
+
+/// The answer is shown under the skeleton in a lighter color, so it reads as an aside.
+const ANSWER_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+
+/// Watches the round: sees what the guessers see, plus the answer, but never guesses.
+pub enum Spectator {
+	WaitingForDrawer,
+	/// The seconds left, the skeleton and the answer, once the server has sent it.
+	Watching(u32, String, String),
+	Done(bool, String, Scoreboard),
+}
+
+impl Spectator {
+	pub fn new() -> Self {
+		Spectator::WaitingForDrawer
+	}
+}
+
+impl Player for Spectator {
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
+		match self {
+			Spectator::WaitingForDrawer => {
+				center_text(font, glyphs, "Waiting for Drawer", CENTER, 50.0, c, g);
+			},
+
+			Spectator::Watching(time, skeleton, answer) => {
+				render_skeleton(font, glyphs, skeleton, c, g);
+				if !answer.is_empty() {
+					let mut answer_font = Text::new_color(ANSWER_COLOR, 20);
+					center_text(&mut answer_font, glyphs, &format!("({})", answer), CENTER, 85.0, c, g);
+				}
+
+				font.draw(
+					&time.to_string(),
+					glyphs,
+					&c.draw_state,
+					c.transform.trans(10.0, 30.0), g
+				).unwrap();
+			},
+
+			Spectator::Done(guessed, word, scoreboard) => {
+				if *guessed {
+					center_text(font, glyphs, "Guessed!", CENTER, 150.0, c, g);
+				} else {
+					center_text(font, glyphs, "Time's Up", CENTER, 150.0, c, g);
+				}
+
+				center_text(font, glyphs, &format!("'{}'", word), CENTER, 250.0, c, g);
+				scoreboard.render(glyphs, 320.0, c, g);
+			}
+		};
+	}
+
+	fn process_action(&self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> {
+		if let GameAction::SwapRoles(drawer_id) = action {
+			return Some(role_for(communications, drawer_id))
+		}
+
+		match self {
+			Spectator::WaitingForDrawer => {
+				match action {
+					GameAction::SetWordSkeleton(skeleton) => {
+						Some(Box::new(Spectator::Watching(communications.settings().round_seconds, skeleton, String::new())))
+					},
+
+					// Only a replay of the drawer's own recording starts with the answer.
+					GameAction::Answer(answer) => {
+						Some(Box::new(Spectator::Watching(communications.settings().round_seconds, String::new(), answer)))
+					},
+
+					_ => None
+				}
+			},
+
+			Spectator::Watching(t, skeleton, answer) => {
+				match action {
+					GameAction::SetTimeRemaining(time) => {
+						Some(Box::new(Spectator::Watching(time, skeleton.clone(), answer.clone())))
+					},
+
+					GameAction::SetWordSkeleton(skeleton) => {
+						Some(Box::new(Spectator::Watching(*t, skeleton, answer.clone())))
+					},
+
+					GameAction::Answer(answer) => {
+						Some(Box::new(Spectator::Watching(*t, skeleton.clone(), answer)))
+					},
+
+					GameAction::GameOver(word) => {
+						communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+						Some(Box::new(Spectator::Done(false, word, communications.scoreboard())))
+					},
+
+					GameAction::GuessResult(_, Verdict::Correct(word)) => {
+						communications.send_canvas_op(CanvasOperation::Save(word.clone()));
+						Some(Box::new(Spectator::Done(true, word, communications.scoreboard())))
+					},
+
+					_ => None
+				}
+			},
+
+			Spectator::Done(guessed, word, _) => {
+				match action {
+					GameAction::ScoreUpdate(_) => {
+						Some(Box::new(Spectator::Done(*guessed, word.clone(), communications.scoreboard())))
+					},
+
+					_ => None
+				}
+			}
+		}
+	}
+}
@@ -2,7 +2,9 @@ use piston_window::*;
 use crate::game::game_action::GameAction;
 use crate::game::communications::Communications;
 use crate::game::text_util::{Glyphs, *};
-use crate::game::player::Player;
+use crate::layout::CENTER;
+use crate::game::player::{Player, role_for};
+use crate::game::tools::Tools;
 
 pub struct WaitingPlayer {
 	address: String,
@@ -11,33 +13,46 @@ pub struct WaitingPlayer {
 impl WaitingPlayer {
 	pub fn new(address: String) -> Self {
 		Self {
-			address: address,
+			address,
 		}
 	}
 }
 
 impl Player for WaitingPlayer {
-	fn render(self: &Self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
-		center_text(font, glyphs, "Waiting for Connection...", 400.0, 150.0, c, g);
-		center_text(font, glyphs, &self.address, 400.0, 250.0, c, g);
+	fn render(&self, font: &mut Text, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d, _device: &mut gfx_device_gl::Device) {
+		center_text(font, glyphs, "Waiting for Connection...", CENTER, 150.0, c, g);
+		center_text(font, glyphs, &self.address, CENTER, 250.0, c, g);
 	}
 
-	fn process_action(self: &Self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> { 
+	fn process_action(&self, communications: &mut Communications, action: GameAction) -> Option<Box<dyn Player + Send>> { 
+		let tools = Tools::new(false, communications.canvas_size());
 		match action {
+			GameAction::SwapRoles(drawer_id) => {
+				return Some(role_for(communications, drawer_id));
+			},
+
 			GameAction::LeftClick(x, y) => {
-				communications.send_action(GameAction::Draw(x, y));
+				communications.send_action(GameAction::Draw(x, y, tools.color(), tools.brush()));
 			},
 
 			GameAction::RightClick(x, y) => {
-				communications.send_action(GameAction::Erase(x, y));
+				communications.send_action(GameAction::Erase(x, y, tools.brush()));
 			},
 
 			GameAction::LeftClickDrag(x1, y1, x2, y2) => {
-				communications.send_action(GameAction::DrawLine(x1, y1, x2, y2));
+				communications.send_action(GameAction::DrawLine(x1, y1, x2, y2, tools.color(), tools.brush()));
 			},
 
 			GameAction::RightClickDrag(x1, y1, x2, y2) => {
-				communications.send_action(GameAction::EraseLine(x1, y1, x2, y2));
+				communications.send_action(GameAction::EraseLine(x1, y1, x2, y2, tools.brush()));
+			},
+
+			GameAction::UndoShortcut => {
+				communications.send_action(GameAction::Undo);
+			},
+
+			GameAction::RedoShortcut => {
+				communications.send_action(GameAction::Redo);
 			},
 
 			_ => {}
@@ -0,0 +1,70 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use piston_window::*;
+use crate::game::text_util::{Glyphs, metrics};
+
+/// Where each client appends its final scoreboard when the window is closed.
+pub const SCORES_FILE: &str = "scores.txt";
+
+/// Every player's running total, highest first.
+#[derive(Debug, Clone, Default)]
+pub struct Scoreboard {
+	entries: Vec<(String, u32)>,
+}
+
+impl Scoreboard {
+	/// Pairs the server's totals with the names in the roster; players without points yet
+	/// are listed with zero.
+	pub fn new(roster: &[(u32, String)], scores: &[(u32, u32)]) -> Self {
+		let mut entries: Vec<(String, u32)> = roster.iter().map(|(id, name)| {
+			let score = scores.iter()
+				.find(|(player_id, _)| player_id == id)
+				.map_or(0, |(_, score)| *score);
+			(name.clone(), score)
+		}).collect();
+
+		entries.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+		Scoreboard { entries }
+	}
+
+	/// The names sharing the highest score, if anyone has scored.
+	pub fn leaders(&self) -> Vec<&str> {
+		let top = self.entries.first().map_or(0, |(_, score)| *score);
+		self.entries.iter()
+			.filter(|(_, score)| top > 0 && *score == top)
+			.map(|(name, _)| name.as_str())
+			.collect()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn render(&self, glyphs: &mut Glyphs<'_>, y: f64, c: Context, g: &mut G2d) {
+		let font = Text::new_color([0.0, 0.0, 0.0, 1.0], 24);
+
+		for (i, (name, score)) in self.entries.iter().enumerate() {
+			let y = y + 30.0 * (i as f64);
+			font.draw(name, glyphs, &c.draw_state, c.transform.trans(250.0, y), g).unwrap();
+
+			let score = score.to_string();
+			let w = metrics(&font, &score, glyphs);
+			font.draw(&score, glyphs, &c.draw_state, c.transform.trans(550.0 - w, y), g).unwrap();
+		}
+	}
+
+	/// Appends the scoreboard to `path` as a block headed by the current unix time.
+	pub fn append_to(&self, path: &Path) -> io::Result<()> {
+		let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+		let mut text = format!("[{}]\n", time);
+		for (name, score) in self.entries.iter() {
+			text.push_str(&format!("{}: {}\n", name, score));
+		}
+		text.push('\n');
+
+		OpenOptions::new().create(true).append(true).open(path)?.write_all(text.as_bytes())
+	}
+}
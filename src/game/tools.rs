@@ -0,0 +1,129 @@
+use piston_window::*;
+use crate::canvas::{Brush, BrushShape, Color};
+use crate::game::game_action::GameAction;
+use crate::game::palette::{self, PALETTE, DEFAULT_COLOR};
+use crate::game::text_util::Glyphs;
+
+/// The drawer's current color and brush, and whether clicks paint or flood fill.
+#[derive(Debug, Clone, Copy)]
+pub struct Tools {
+	color: usize,
+	brush: Brush,
+	can_fill: bool,
+	filling: bool,
+	/// Whether the left button went down on a swatch, so dragging from there paints nothing.
+	picking: bool,
+	/// Width and height of the canvas, to find the swatch under a click.
+	canvas_size: u32,
+}
+
+impl Tools {
+	/// `can_fill` is false when the server did not negotiate the fill capability.
+	pub fn new(can_fill: bool, canvas_size: u32) -> Self {
+		Tools {
+			color: DEFAULT_COLOR,
+			brush: Brush::default().with_antialiasing(true).with_smoothing(true),
+			can_fill,
+			filling: false,
+			picking: false,
+			canvas_size,
+		}
+	}
+
+	pub fn color(&self) -> Color {
+		PALETTE[self.color]
+	}
+
+	pub fn brush(&self) -> Brush {
+		self.brush
+	}
+
+	pub fn filling(&self) -> bool {
+		self.filling
+	}
+
+	pub fn picking(&self) -> bool {
+		self.picking
+	}
+
+	/// Applies a tool hotkey or swatch click, returning the new tools if the action was one.
+	pub fn process_action(&self, action: &GameAction) -> Option<Tools> {
+		let mut tools = *self;
+		match action {
+			GameAction::TypeNumber(n) => {
+				tools.color = palette::swatch_for_number(*n)?;
+			},
+
+			GameAction::LeftClick(x, y) => {
+				tools.color = palette::swatch_at(*x, *y, self.canvas_size)?;
+				tools.picking = true;
+			},
+
+			GameAction::MouseRelease if self.picking => {
+				tools.picking = false;
+			},
+
+			GameAction::TypeSymbol('[') => {
+				tools.brush.size = tools.brush.size.saturating_sub(1).max(1);
+			},
+
+			GameAction::TypeSymbol(']') => {
+				tools.brush.size = (tools.brush.size + 1).min(Brush::MAX_SIZE);
+			},
+
+			GameAction::TypeLetter('f') if self.can_fill => {
+				tools.filling = !tools.filling;
+			},
+
+			GameAction::TypeLetter('s') => {
+				tools.brush.shape = match tools.brush.shape {
+					BrushShape::Round => BrushShape::Square,
+					BrushShape::Square => BrushShape::Round,
+				};
+			},
+
+			GameAction::TypeLetter('a') => {
+				tools.brush.antialias = !tools.brush.antialias;
+			},
+
+			GameAction::TypeLetter('c') => {
+				tools.brush.smooth = !tools.brush.smooth;
+			},
+
+			_ => return None,
+		};
+
+		Some(tools)
+	}
+
+	pub fn render(&self, glyphs: &mut Glyphs<'_>, c: Context, g: &mut G2d) {
+		palette::render(self.color, c, g);
+
+		let shape = match self.brush.shape {
+			BrushShape::Round => "round",
+			BrushShape::Square => "square",
+		};
+
+		let mut help = if self.filling {
+			"fill".to_owned()
+		} else {
+			let on = |flag| if flag { "on" } else { "off" };
+			format!(
+				"brush {} {}  [ ] size  [s] shape  [a] aa {}  [c] curves {}",
+				self.brush.size, shape, on(self.brush.antialias), on(self.brush.smooth)
+			)
+		};
+
+		if self.can_fill {
+			help.push_str("  [f] toggle fill");
+		}
+
+		let font = Text::new_color([0.0, 0.0, 0.0, 1.0], 16);
+		font.draw(
+			&help,
+			glyphs,
+			&c.draw_state,
+			c.transform.trans(10.0, 735.0), g
+		).unwrap();
+	}
+}
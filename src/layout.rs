@@ -0,0 +1,75 @@
+use piston_window::*;
+
+/// Everything on screen is laid out in a square view this many pixels across, which is then
+/// scaled to fit the window.
+pub const VIEW_SIZE: f64 = 800.0;
+
+/// The middle of the view, where most text is centered.
+pub const CENTER: f64 = VIEW_SIZE / 2.0;
+
+/// Fills the space either side of the view when the window is not square.
+pub const LETTERBOX_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.0];
+
+/// Where the view sits in the window: as large as fits, centered, with bars along the
+/// leftover edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+	scale: f64,
+	offset: [f64; 2],
+}
+
+impl Layout {
+	pub fn new(window_size: [f64; 2]) -> Self {
+		let scale = window_size[0].min(window_size[1]).max(0.0) / VIEW_SIZE;
+		Layout {
+			scale,
+			offset: [
+				(window_size[0] - VIEW_SIZE * scale) * 0.5,
+				(window_size[1] - VIEW_SIZE * scale) * 0.5,
+			],
+		}
+	}
+
+	pub fn scale(&self) -> f64 {
+		self.scale
+	}
+
+	/// The window's context changed to draw in view coordinates.
+	pub fn transform(&self, c: Context) -> Context {
+		c.trans(self.offset[0], self.offset[1]).scale(self.scale, self.scale)
+	}
+
+	/// The point of the view under a window position, or `None` over the bars.
+	pub fn to_view(&self, position: [f64; 2]) -> Option<[f64; 2]> {
+		if self.scale <= 0.0 {
+			return None;
+		}
+
+		let x = (position[0] - self.offset[0]) / self.scale;
+		let y = (position[1] - self.offset[1]) / self.scale;
+		if (0.0..VIEW_SIZE).contains(&x) && (0.0..VIEW_SIZE).contains(&y) {
+			Some([x, y])
+		} else {
+			None
+		}
+	}
+
+	/// The pixel under a window position of a `width` by `height` canvas stretched over the
+	/// whole view.
+	pub fn to_canvas(&self, position: [f64; 2], width: u32, height: u32) -> Option<(u32, u32)> {
+		self.to_view(position).map(|[x, y]| {
+			((x * width as f64 / VIEW_SIZE) as u32, (y * height as f64 / VIEW_SIZE) as u32)
+		})
+	}
+}
+
+impl Default for Layout {
+	fn default() -> Self {
+		Layout::new([VIEW_SIZE; 2])
+	}
+}
+
+/// Blanks the view, leaving the bars alone.
+pub fn clear_view(c: Context, g: &mut G2d) {
+	rectangle([1.0; 4], [0.0, 0.0, VIEW_SIZE, VIEW_SIZE], c.transform, g);
+}
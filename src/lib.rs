@@ -0,0 +1,12 @@
+extern crate piston_window;
+extern crate image as im;
+
+pub mod canvas;
+pub mod config;
+pub mod gallery;
+pub mod game;
+pub mod layout;
+pub mod message;
+pub mod pictlog;
+pub mod server;
+pub mod transport;
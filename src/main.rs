@@ -1,22 +1,49 @@
 extern crate piston_window;
-extern crate image as im;
-
-mod game;
-mod canvas;
 
+use std::{env, process};
 use piston_window::*;
-use crate::canvas::GameCanvas;
-use crate::game::Game;
+use pictionary::canvas::GameCanvas;
+use pictionary::config::{Config, Role};
+use pictionary::gallery;
+use pictionary::layout::{LETTERBOX_COLOR, Layout, VIEW_SIZE};
+use pictionary::game::{Game, Hotseat};
+
+/// How far back `--gallery` looks for drawings when no number of hours is given.
+const GALLERY_HOURS: u64 = 12;
 
 fn main() {
-	let size = 100;
+	let args: Vec<String> = env::args().collect();
+	if args.get(1).map(String::as_str) == Some("--gallery") {
+		let hours = args.get(2).map_or(Ok(GALLERY_HOURS), |hours| hours.parse()).unwrap_or_else(|_| {
+			eprintln!("usage: pictionary --gallery [hours]");
+			process::exit(2);
+		});
+
+		match gallery::save_gallery(hours) {
+			Ok(path) => println!("Wrote {}", path.display()),
+			Err(e) => {
+				eprintln!("{}", e);
+				process::exit(1);
+			}
+		}
+
+		return;
+	}
+
+	let config = Config::load(&args[1..]).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(2);
+	});
+
+	// A guest's canvas is reallocated if the host picked another size.
+	let size = config.settings.canvas_size;
     let mut window: PistonWindow = WindowSettings::new(
 		"Pictionary",
-		[size * 8; 2]
+		[VIEW_SIZE; 2]
 	)
 	.exit_on_esc(true)
 	.graphics_api(OpenGL::V4_1)
-	.resizable(false)
+	.resizable(true)
 	.build()
 	.unwrap();
 
@@ -24,16 +51,48 @@ fn main() {
 	let mut glyphs = window.load_font(assets.join("FiraSans-Regular.ttf")).unwrap();
 	let mut font = text::Text::new_color([0.0, 0.0, 0.0, 1.0], 32);
 
+	if let Role::Local(names) = config.role {
+		let mut hotseat = Hotseat::new(&mut window, names, config.settings).unwrap_or_else(|e| {
+			eprintln!("{}", e);
+			process::exit(1);
+		});
+
+		while let Some(e) = window.next() {
+			if let Some(args) = e.render_args() {
+				hotseat.pre_render();
+
+				let layout = Layout::new(args.window_size);
+				window.draw_2d(&e, |c, g, device| {
+					glyphs.factory.encoder.flush(device);
+					clear(LETTERBOX_COLOR, g);
+					let c = layout.transform(c);
+					hotseat.render(&mut font, &mut glyphs, c, g, device);
+				});
+			}
+
+			hotseat.process_event(e);
+		}
+
+		if let Err(e) = hotseat.save_scores() {
+			eprintln!("Could not save scores: {}", e);
+		}
+
+		return;
+	}
+
 	let mut canvas = GameCanvas::new(&mut window, size, size);
 	
-	let game = Game::new(canvas.op_sender.clone());
+	let game = Game::new(config, canvas.op_sender.clone());
 
     while let Some(e) = window.next() {
-        if e.render_args().is_some() {
+        if let Some(args) = e.render_args() {
 			canvas.pre_render();
 			
+			let layout = Layout::new(args.window_size);
             window.draw_2d(&e, |c, g, device| {
 				glyphs.factory.encoder.flush(device);
+				clear(LETTERBOX_COLOR, g);
+				let c = layout.transform(c);
 				canvas.render(c, g, device);
                 game.lock().unwrap().render(&mut font, &mut glyphs, c, g, device);				
             });
@@ -41,4 +100,9 @@ fn main() {
 
 		game.lock().unwrap().process_event(e)
     }
+
+	let saved = game.lock().unwrap().save_scores();
+	if let Err(e) = saved {
+		eprintln!("Could not save scores: {}", e);
+	}
 }
\ No newline at end of file
@@ -0,0 +1,859 @@
+use std::{error, fmt};
+use std::io::{self, Read, Write};
+
+use crate::canvas::{Brush, BrushShape, Color};
+
+/// Bumped whenever the layout of an existing message changes; peers must match exactly.
+pub const PROTOCOL_VERSION: u32 = 7;
+
+/// Bit set of optional message types a peer understands. New message types get a capability
+/// bit rather than a version bump, so a peer without it can still play and is simply never
+/// sent those messages.
+pub type Capabilities = u32;
+
+/// Optional message types, one bit each.
+pub mod capability {
+	use super::Capabilities;
+
+	/// `GameMessage::Fill`, the paint bucket.
+	pub const FILL: Capabilities = 1 << 0;
+
+	/// `GameMessage::Undo` and `GameMessage::Redo`.
+	pub const UNDO: Capabilities = 1 << 1;
+
+	/// `GameMessage::ScoreUpdate`, the running totals.
+	pub const SCORES: Capabilities = 1 << 2;
+
+	/// `GameMessage::Settings`, the host's round settings.
+	pub const SETTINGS: Capabilities = 1 << 3;
+
+	/// `GameMessage::MatchOver`, the final results of a fixed-length match.
+	pub const MATCH: Capabilities = 1 << 4;
+
+	/// `GameMessage::CanvasSnapshot`, the whole picture at once.
+	pub const SNAPSHOT: Capabilities = 1 << 5;
+
+	/// `GameMessage::Answer`, the word told to spectators.
+	pub const SPECTATE: Capabilities = 1 << 6;
+
+	/// `GameMessage::Polyline` and `GameMessage::ErasePolyline`, runs of lines sent as one
+	/// message.
+	pub const POLYLINE: Capabilities = 1 << 7;
+}
+
+/// Every capability this build understands.
+pub const CAPABILITIES: Capabilities = capability::FILL | capability::UNDO | capability::SCORES |
+	capability::SETTINGS | capability::MATCH | capability::SNAPSHOT | capability::SPECTATE |
+	capability::POLYLINE;
+
+/// Attempts a client makes to get a dropped connection back, and the seconds it waits before
+/// the first; each wait after that is twice as long as the one before.
+pub const RECONNECT_ATTEMPTS: u32 = 6;
+pub const RECONNECT_DELAY_SECONDS: u32 = 1;
+
+/// The seconds a client spends waiting between its reconnect attempts altogether; the server
+/// keeps a dropped player's place for at least this long.
+pub const RECONNECT_SECONDS: u32 = RECONNECT_DELAY_SECONDS * ((1 << RECONNECT_ATTEMPTS) - 1);
+
+/// Longest string (in bytes) a peer may send; anything longer is treated as a corrupt stream.
+const MAX_STRING_LEN: usize = 1024;
+
+/// Longest list (roster, word choices) a peer may send.
+const MAX_LIST_LEN: usize = 256;
+
+/// Largest canvas snapshot, in pixels, a peer may send.
+const MAX_SNAPSHOT_PIXELS: usize = 1024 * 1024;
+
+/// Bytes per pixel in a canvas snapshot.
+const RGBA: usize = 4;
+
+/// Snapshot runs are a length byte followed by the repeated pixel.
+const MAX_RUN_LEN: usize = 255;
+const RUN_SIZE: usize = 1 + RGBA;
+
+/// Most points a peer may send in one polyline.
+pub const MAX_POLYLINE_POINTS: usize = 4096;
+
+/// A polyline's points after the first are sent as the zigzag-encoded difference from the one
+/// before, 7 bits to a byte; no difference between two `u16`s needs more than this many bytes.
+const MAX_DELTA_LEN: usize = 3;
+
+const MESSAGE_DATA_SIZE: &[usize] = &[
+	14, // draw
+	4, // set time remaining
+	4, // set word skeleton
+	8, // guess
+	9, // guess result
+	4, // game over
+	4, // swap roles
+	11, // erase
+	22, // draw line
+	19, // erase line
+	17, // hello
+	4, // leave
+	8, // roster
+	4, // word choices
+	4, // pick word
+	0, // next round
+	4, // reject
+	11, // fill
+	0, // undo
+	0, // redo
+	4, // score update
+	12, // settings
+	4, // match over
+	12, // canvas snapshot
+	4, // answer
+	10, // polyline
+	7, // erase polyline
+];
+
+#[derive(Debug)]
+pub enum ProtocolError {
+	Eof,
+	UnknownMessage(u8),
+	StringTooLong(usize),
+	ListTooLong(usize),
+	InvalidUtf8,
+	/// A snapshot whose runs do not add up to its width times its height.
+	InvalidSnapshot,
+	/// A polyline with too many points, or one that runs off the coordinate range.
+	InvalidPolyline,
+	/// A brush of no size, or larger than `Brush::MAX_SIZE`.
+	InvalidBrush(u8),
+	Io(io::Error),
+}
+
+impl fmt::Display for ProtocolError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ProtocolError::Eof => write!(f, "connection closed"),
+			ProtocolError::UnknownMessage(id) => write!(f, "unknown message id {}", id),
+			ProtocolError::StringTooLong(len) => write!(f, "string of {} bytes exceeds the limit of {}", len, MAX_STRING_LEN),
+			ProtocolError::ListTooLong(len) => write!(f, "list of {} entries exceeds the limit of {}", len, MAX_LIST_LEN),
+			ProtocolError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+			ProtocolError::InvalidSnapshot => write!(f, "canvas snapshot does not match its dimensions"),
+			ProtocolError::InvalidPolyline => write!(f, "polyline is too long or leaves the canvas coordinates"),
+			ProtocolError::InvalidBrush(size) => write!(f, "brush size {} is outside 1 to {}", size, Brush::MAX_SIZE),
+			ProtocolError::Io(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl error::Error for ProtocolError {}
+
+impl From<io::Error> for ProtocolError {
+	fn from(e: io::Error) -> Self {
+		match e.kind() {
+			io::ErrorKind::UnexpectedEof => ProtocolError::Eof,
+			_ => ProtocolError::Io(e),
+		}
+	}
+}
+
+/// How the server judged a guess.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+	Wrong,
+	/// Within a couple of typos of the word; only the guesser and the drawer are told.
+	Close,
+	Correct(String),
+}
+
+/// One round of a finished match, as listed on the final results screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundResult {
+	pub word: String,
+	/// The name of whoever guessed the word, if anyone did.
+	pub winner: Option<String>,
+	/// How long the round took.
+	pub seconds: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameMessage {
+	Draw(u32, u32, Color, Brush),
+	SetTimeRemaining(u32),
+	SetWordSkeleton(String),
+	Guess(u32, String),
+	GuessResult(u32, Verdict),
+	GameOver(String),
+	SwapRoles(u32),
+	Erase(u32, u32, Brush),
+	DrawLine(u32, u32, u32, u32, Color, Brush),
+	EraseLine(u32, u32, u32, u32, Brush),
+	/// Sent first by both sides. A client that is `spectating` only watches the game. The
+	/// server's carries the width and height of the canvas everyone draws on; clients send 0.
+	Hello { protocol_version: u32, client_name: String, capabilities: Capabilities, canvas_size: u32, spectating: bool },
+	Leave(u32),
+	Roster(u32, Vec<(u32, String)>),
+	WordChoices(Vec<String>),
+	PickWord(u32),
+	NextRound,
+	Reject(String),
+	Fill(u32, u32, Color),
+	Undo,
+	Redo,
+	/// Every player's running total as (player id, score).
+	ScoreUpdate(Vec<(u32, u32)>),
+	/// The host's settings that guests need to know, sent when they join.
+	Settings { round_seconds: u32, choices: u32, rounds: u32 },
+	/// Every round of the match; the server closes the connection right after.
+	MatchOver(Vec<RoundResult>),
+	/// The whole canvas as its width, its height and its RGBA pixels row by row; run-length
+	/// encoded on the wire.
+	CanvasSnapshot(u32, u32, Vec<u8>),
+	/// The word being drawn, for spectators.
+	Answer(String),
+	/// Lines joining each point to the next, all in the same stroke. The drawer gathers a
+	/// drag into one of these rather than sending every line of it on its own.
+	Polyline { color: Color, brush: Brush, points: Vec<(u16, u16)> },
+	/// A `Polyline` that erases.
+	ErasePolyline { brush: Brush, points: Vec<(u16, u16)> },
+}
+
+/// Reads one message from any byte stream: a socket, a file or a buffer in memory.
+pub fn parse_game_message<R: Read>(stream: &mut R) -> Result<GameMessage, ProtocolError> {
+	let mut id = [0u8; 1];
+	stream.read_exact(&mut id)?;
+
+	let id = id[0] as usize;
+	if id >= MESSAGE_DATA_SIZE.len() {
+		return Err(ProtocolError::UnknownMessage(id as u8));
+	}
+
+	let s = MESSAGE_DATA_SIZE[id];
+	let mut bytes = vec![0; s];
+	if s > 0 {
+		stream.read_exact(&mut bytes[..s])?;
+	}
+
+	Ok(match id {
+		0 => {
+			GameMessage::Draw(
+				u32_from_bytes(&bytes[0..4]), 
+				u32_from_bytes(&bytes[4..8]),
+				color_from_bytes(&bytes[8..11]),
+				brush_from_bytes(&bytes[11..14])?
+			)
+		}
+
+		1 => {
+			GameMessage::SetTimeRemaining(
+				u32_from_bytes(&bytes[0..4])
+			)
+		},
+
+		2 => {
+			GameMessage::SetWordSkeleton(read_string(u32_from_bytes(&bytes[0..4]) as usize, stream)?)
+		},
+
+		3 => {	
+			GameMessage::Guess(
+				u32_from_bytes(&bytes[0..4]),
+				read_string(u32_from_bytes(&bytes[4..8]) as usize, stream)?
+			)
+		},
+
+		4 => {
+			let len = u32_from_bytes(&bytes[5..9]) as usize;
+			GameMessage::GuessResult(u32_from_bytes(&bytes[0..4]), match bytes[4] {
+				1 => Verdict::Correct(read_string(len, stream)?),
+				2 => Verdict::Close,
+				_ => Verdict::Wrong,
+			})
+		},
+
+		5 => {
+			GameMessage::GameOver(read_string(u32_from_bytes(&bytes[0..4]) as usize, stream)?)
+		},
+
+		6 => GameMessage::SwapRoles(u32_from_bytes(&bytes[0..4])),
+
+		7 => GameMessage::Erase(
+			u32_from_bytes(&bytes[0..4]), 
+			u32_from_bytes(&bytes[4..8]),
+			brush_from_bytes(&bytes[8..11])?
+		),
+
+		8 => GameMessage::DrawLine(
+			u32_from_bytes(&bytes[0..4]), 
+			u32_from_bytes(&bytes[4..8]),
+			u32_from_bytes(&bytes[8..12]),
+			u32_from_bytes(&bytes[12..16]),
+			color_from_bytes(&bytes[16..19]),
+			brush_from_bytes(&bytes[19..22])?
+		),
+
+		9 => GameMessage::EraseLine(
+			u32_from_bytes(&bytes[0..4]), 
+			u32_from_bytes(&bytes[4..8]),
+			u32_from_bytes(&bytes[8..12]),
+			u32_from_bytes(&bytes[12..16]),
+			brush_from_bytes(&bytes[16..19])?
+		),
+
+		10 => GameMessage::Hello {
+			protocol_version: u32_from_bytes(&bytes[0..4]),
+			capabilities: u32_from_bytes(&bytes[4..8]),
+			canvas_size: u32_from_bytes(&bytes[8..12]),
+			spectating: bytes[12] != 0,
+			client_name: read_string(u32_from_bytes(&bytes[13..17]) as usize, stream)?,
+		},
+
+		11 => GameMessage::Leave(u32_from_bytes(&bytes[0..4])),
+
+		12 => {
+			let count = read_list_len(u32_from_bytes(&bytes[4..8]))?;
+			let mut players = Vec::new();
+			for _ in 0..count {
+				let id = read_u32(stream)?;
+				let len = read_u32(stream)?;
+				players.push((id, read_string(len as usize, stream)?));
+			}
+
+			GameMessage::Roster(u32_from_bytes(&bytes[0..4]), players)
+		},
+
+		13 => {
+			let count = read_list_len(u32_from_bytes(&bytes[0..4]))?;
+			let mut words = Vec::new();
+			for _ in 0..count {
+				let len = read_u32(stream)?;
+				words.push(read_string(len as usize, stream)?);
+			}
+
+			GameMessage::WordChoices(words)
+		},
+
+		14 => GameMessage::PickWord(u32_from_bytes(&bytes[0..4])),
+
+		15 => GameMessage::NextRound,
+
+		16 => GameMessage::Reject(read_string(u32_from_bytes(&bytes[0..4]) as usize, stream)?),
+
+		17 => GameMessage::Fill(
+			u32_from_bytes(&bytes[0..4]),
+			u32_from_bytes(&bytes[4..8]),
+			color_from_bytes(&bytes[8..11])
+		),
+
+		18 => GameMessage::Undo,
+
+		19 => GameMessage::Redo,
+
+		20 => {
+			let count = read_list_len(u32_from_bytes(&bytes[0..4]))?;
+			let mut scores = Vec::new();
+			for _ in 0..count {
+				scores.push((read_u32(stream)?, read_u32(stream)?));
+			}
+
+			GameMessage::ScoreUpdate(scores)
+		},
+
+		21 => GameMessage::Settings {
+			round_seconds: u32_from_bytes(&bytes[0..4]),
+			choices: u32_from_bytes(&bytes[4..8]),
+			rounds: u32_from_bytes(&bytes[8..12]),
+		},
+
+		22 => {
+			let count = read_list_len(u32_from_bytes(&bytes[0..4]))?;
+			let mut results = Vec::new();
+			for _ in 0..count {
+				let len = read_u32(stream)?;
+				let word = read_string(len as usize, stream)?;
+				let len = read_u32(stream)?;
+				let winner = read_string(len as usize, stream)?;
+				results.push(RoundResult {
+					word,
+					winner: if winner.is_empty() { None } else { Some(winner) },
+					seconds: read_u32(stream)?,
+				});
+			}
+
+			GameMessage::MatchOver(results)
+		},
+
+		23 => {
+			let (width, height) = (u32_from_bytes(&bytes[0..4]), u32_from_bytes(&bytes[4..8]));
+			let pixels = width as usize * height as usize;
+			let count = u32_from_bytes(&bytes[8..12]) as usize;
+			if pixels > MAX_SNAPSHOT_PIXELS || count > pixels {
+				return Err(ProtocolError::InvalidSnapshot);
+			}
+
+			let mut runs = vec![0; count * RUN_SIZE];
+			stream.read_exact(&mut runs)?;
+			GameMessage::CanvasSnapshot(width, height, decode_runs(&runs, pixels)?)
+		},
+
+		24 => GameMessage::Answer(read_string(u32_from_bytes(&bytes[0..4]) as usize, stream)?),
+
+		25 => GameMessage::Polyline {
+			color: color_from_bytes(&bytes[0..3]),
+			brush: brush_from_bytes(&bytes[3..6])?,
+			points: read_points(u32_from_bytes(&bytes[6..10]) as usize, stream)?,
+		},
+
+		26 => GameMessage::ErasePolyline {
+			brush: brush_from_bytes(&bytes[0..3])?,
+			points: read_points(u32_from_bytes(&bytes[3..7]) as usize, stream)?,
+		},
+
+		_ => unreachable!()
+	})
+}
+
+impl GameMessage {
+	fn id(&self) -> u8 {
+		match &self {
+			GameMessage::Draw(_, _, _, _) => 0,
+			GameMessage::SetTimeRemaining(_) => 1,
+			GameMessage::SetWordSkeleton(_) => 2,
+			GameMessage::Guess(_, _) => 3,
+			GameMessage::GuessResult(_, _) => 4,
+			GameMessage::GameOver(_) => 5,
+			GameMessage::SwapRoles(_) => 6,
+			GameMessage::Erase(_, _, _) => 7,
+			GameMessage::DrawLine(_, _, _, _, _, _) => 8,
+			GameMessage::EraseLine(_, _, _, _, _) => 9,
+			GameMessage::Hello { .. } => 10,
+			GameMessage::Leave(_) => 11,
+			GameMessage::Roster(_, _) => 12,
+			GameMessage::WordChoices(_) => 13,
+			GameMessage::PickWord(_) => 14,
+			GameMessage::NextRound => 15,
+			GameMessage::Reject(_) => 16,
+			GameMessage::Fill(_, _, _) => 17,
+			GameMessage::Undo => 18,
+			GameMessage::Redo => 19,
+			GameMessage::ScoreUpdate(_) => 20,
+			GameMessage::Settings { .. } => 21,
+			GameMessage::MatchOver(_) => 22,
+			GameMessage::CanvasSnapshot(_, _, _) => 23,
+			GameMessage::Answer(_) => 24,
+			GameMessage::Polyline { .. } => 25,
+			GameMessage::ErasePolyline { .. } => 26,
+		}
+	}
+
+	/// Capabilities a peer must have negotiated to be sent this message.
+	pub fn required_capabilities(&self) -> Capabilities {
+		match self {
+			GameMessage::Fill(_, _, _) => capability::FILL,
+			GameMessage::Undo | GameMessage::Redo => capability::UNDO,
+			GameMessage::ScoreUpdate(_) => capability::SCORES,
+			GameMessage::Settings { .. } => capability::SETTINGS,
+			GameMessage::MatchOver(_) => capability::MATCH,
+			GameMessage::CanvasSnapshot(_, _, _) => capability::SNAPSHOT,
+			GameMessage::Answer(_) => capability::SPECTATE,
+			GameMessage::Polyline { .. } | GameMessage::ErasePolyline { .. } => capability::POLYLINE,
+			_ => 0,
+		}
+	}
+
+	/// Whether every canvas coordinate in the message lies on a `size` by `size` canvas, and
+	/// a snapshot is of exactly that size. Messages without coordinates always fit.
+	pub fn in_bounds(&self, size: u32) -> bool {
+		let on_canvas = |x: u32, y: u32| x < size && y < size;
+		match *self {
+			GameMessage::Draw(x, y, _, _) | GameMessage::Erase(x, y, _) | GameMessage::Fill(x, y, _) => on_canvas(x, y),
+			GameMessage::DrawLine(x1, y1, x2, y2, _, _) | GameMessage::EraseLine(x1, y1, x2, y2, _) => {
+				on_canvas(x1, y1) && on_canvas(x2, y2)
+			},
+			GameMessage::CanvasSnapshot(width, height, _) => width == size && height == size,
+			GameMessage::Polyline { ref points, .. } | GameMessage::ErasePolyline { ref points, .. } => points.iter().all(|&(x, y)| on_canvas(x as u32, y as u32)),
+			_ => true,
+		}
+	}
+
+	/// A polyline as the `DrawLine`s or `EraseLine`s it is made of, for peers that cannot read
+	/// it whole. Any other message has no lines.
+	pub fn lines(&self) -> Vec<GameMessage> {
+		let segments = |points: &[(u16, u16)]| -> Vec<(u32, u32, u32, u32)> {
+			points.windows(2).map(|line| {
+				let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+				(x1 as u32, y1 as u32, x2 as u32, y2 as u32)
+			}).collect()
+		};
+
+		match self {
+			GameMessage::Polyline { color, brush, points } => segments(points).into_iter()
+				.map(|(x1, y1, x2, y2)| GameMessage::DrawLine(x1, y1, x2, y2, *color, *brush))
+				.collect(),
+			GameMessage::ErasePolyline { brush, points } => segments(points).into_iter()
+				.map(|(x1, y1, x2, y2)| GameMessage::EraseLine(x1, y1, x2, y2, *brush))
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Writes the message to any byte stream; pass a `&TcpStream` to write to a socket shared
+	/// with its reader.
+	pub fn send<W: Write>(&self, mut stream: W) -> io::Result<()> {
+		stream.write_all(&self.encode())
+	}
+
+	/// The message as it is sent over the wire.
+	pub fn encode(&self) -> Vec<u8> {
+		let mut bytes = vec![self.id()];
+		let push_u32 = |bytes: &mut Vec<u8>, i: u32| {
+			bytes.extend_from_slice(&u32_to_bytes(i));
+		};
+
+		let push_color = |bytes: &mut Vec<u8>, color: &Color| {
+			bytes.extend_from_slice(&[color.r, color.g, color.b]);
+		};
+
+		let push_brush = |bytes: &mut Vec<u8>, brush: &Brush| {
+			let shape = match brush.shape {
+				BrushShape::Round => 0,
+				BrushShape::Square => 1,
+			};
+			let flags = brush.antialias as u8 | (brush.smooth as u8) << 1;
+			bytes.extend_from_slice(&[brush.size, shape, flags]);
+		};
+
+		let push_points = |bytes: &mut Vec<u8>, points: &[(u16, u16)]| {
+			push_u32(bytes, points.len() as u32);
+			if let Some((x, y)) = points.first() {
+				bytes.extend_from_slice(&x.to_be_bytes());
+				bytes.extend_from_slice(&y.to_be_bytes());
+			}
+
+			for line in points.windows(2) {
+				let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+				push_delta(bytes, x2 as i32 - x1 as i32);
+				push_delta(bytes, y2 as i32 - y1 as i32);
+			}
+		};
+
+		let push_string = |bytes: &mut Vec<u8>, s: &String| {
+			let str_bytes = s.as_bytes();
+			bytes.extend_from_slice(&u32_to_bytes(str_bytes.len() as u32));
+			bytes.extend_from_slice(str_bytes);
+		};
+
+		match self {
+			GameMessage::Draw(x, y, color, brush) => {
+				for v in [x, y] {
+					push_u32(&mut bytes, *v);
+				}
+				push_color(&mut bytes, color);
+				push_brush(&mut bytes, brush);
+			},
+
+			GameMessage::SetTimeRemaining(t) => {
+				push_u32(&mut bytes, *t);
+			},
+
+			GameMessage::SetWordSkeleton(str) => {
+				push_string(&mut bytes, str);
+			},
+
+			GameMessage::Guess(id, str) => {
+				push_u32(&mut bytes, *id);
+				push_string(&mut bytes, str);
+			},
+
+			GameMessage::GuessResult(id, verdict) => {
+				push_u32(&mut bytes, *id);
+				match verdict {
+					Verdict::Correct(word) => {
+						bytes.push(1);
+						push_string(&mut bytes, word);
+					},
+
+					Verdict::Close => {
+						bytes.push(2);
+						push_u32(&mut bytes, 0);
+					},
+
+					Verdict::Wrong => {
+						bytes.push(0);
+						push_u32(&mut bytes, 0);
+					},
+				}
+			},
+
+			GameMessage::GameOver(str) => {
+				push_string(&mut bytes, str);
+			},
+
+			GameMessage::SwapRoles(id) => {
+				push_u32(&mut bytes, *id);
+			},
+
+			GameMessage::Erase(x, y, brush) => {
+				for v in [x, y] {
+					push_u32(&mut bytes, *v);
+				}
+				push_brush(&mut bytes, brush);
+			},
+
+			GameMessage::DrawLine(x1, y1, x2, y2, color, brush) => {
+				for v in [x1, y1, x2, y2] {
+					push_u32(&mut bytes, *v);
+				}
+				push_color(&mut bytes, color);
+				push_brush(&mut bytes, brush);
+			},
+
+			GameMessage::EraseLine(x1, y1, x2, y2, brush) => {
+				for v in [x1, y1, x2, y2] {
+					push_u32(&mut bytes, *v);
+				}
+				push_brush(&mut bytes, brush);
+			},
+
+			GameMessage::Hello { protocol_version, client_name, capabilities, canvas_size, spectating } => {
+				push_u32(&mut bytes, *protocol_version);
+				push_u32(&mut bytes, *capabilities);
+				push_u32(&mut bytes, *canvas_size);
+				bytes.push(*spectating as u8);
+				push_string(&mut bytes, client_name);
+			},
+
+			GameMessage::Leave(id) => {
+				push_u32(&mut bytes, *id);
+			},
+
+			GameMessage::Roster(id, players) => {
+				push_u32(&mut bytes, *id);
+				push_u32(&mut bytes, players.len() as u32);
+				for (player_id, name) in players {
+					push_u32(&mut bytes, *player_id);
+					push_string(&mut bytes, name);
+				}
+			},
+
+			GameMessage::WordChoices(words) => {
+				push_u32(&mut bytes, words.len() as u32);
+				for word in words {
+					push_string(&mut bytes, word);
+				}
+			},
+
+			GameMessage::PickWord(index) => {
+				push_u32(&mut bytes, *index);
+			},
+
+			GameMessage::NextRound => {},
+
+			GameMessage::Reject(reason) => {
+				push_string(&mut bytes, reason);
+			},
+
+			GameMessage::Fill(x, y, color) => {
+				for v in [x, y] {
+					push_u32(&mut bytes, *v);
+				}
+				push_color(&mut bytes, color);
+			},
+
+			GameMessage::Undo => {},
+
+			GameMessage::Redo => {},
+
+			GameMessage::ScoreUpdate(scores) => {
+				push_u32(&mut bytes, scores.len() as u32);
+				for (id, score) in scores {
+					push_u32(&mut bytes, *id);
+					push_u32(&mut bytes, *score);
+				}
+			},
+
+			GameMessage::Settings { round_seconds, choices, rounds } => {
+				for v in [round_seconds, choices, rounds] {
+					push_u32(&mut bytes, *v);
+				}
+			},
+
+			GameMessage::MatchOver(results) => {
+				push_u32(&mut bytes, results.len() as u32);
+				for result in results {
+					push_string(&mut bytes, &result.word);
+					push_string(&mut bytes, &result.winner.clone().unwrap_or_default());
+					push_u32(&mut bytes, result.seconds);
+				}
+			},
+
+			GameMessage::CanvasSnapshot(width, height, pixels) => {
+				let runs = encode_runs(pixels);
+				push_u32(&mut bytes, *width);
+				push_u32(&mut bytes, *height);
+				push_u32(&mut bytes, (runs.len() / RUN_SIZE) as u32);
+				bytes.extend_from_slice(&runs);
+			},
+
+			GameMessage::Answer(word) => {
+				push_string(&mut bytes, word);
+			},
+
+			GameMessage::Polyline { color, brush, points } => {
+				push_color(&mut bytes, color);
+				push_brush(&mut bytes, brush);
+				push_points(&mut bytes, points);
+			},
+
+			GameMessage::ErasePolyline { brush, points } => {
+				push_brush(&mut bytes, brush);
+				push_points(&mut bytes, points);
+			},
+		}
+
+		bytes
+	}
+}
+
+fn read_u32<R: Read>(stream: &mut R) -> Result<u32, ProtocolError> {
+	let mut bytes = [0u8; 4];
+	stream.read_exact(&mut bytes)?;
+
+	Ok(u32_from_bytes(&bytes))
+}
+
+/// Writes the difference between two coordinates: zigzag-encoded so that small steps either
+/// way fit in a byte, then 7 bits at a time, low bits first, with the top bit set on every
+/// byte but the last.
+fn push_delta(bytes: &mut Vec<u8>, delta: i32) {
+	let mut zigzag = ((delta << 1) ^ (delta >> 31)) as u32;
+	while zigzag >= 0x80 {
+		bytes.push((zigzag & 0x7f) as u8 | 0x80);
+		zigzag >>= 7;
+	}
+
+	bytes.push(zigzag as u8);
+}
+
+/// Reads `count` points: the first in full, then each as its difference from the one before.
+fn read_points<R: Read>(count: usize, stream: &mut R) -> Result<Vec<(u16, u16)>, ProtocolError> {
+	if count > MAX_POLYLINE_POINTS {
+		return Err(ProtocolError::InvalidPolyline);
+	}
+
+	let mut points = Vec::with_capacity(count);
+	if count > 0 {
+		let mut first = [0u8; 4];
+		stream.read_exact(&mut first)?;
+		points.push((u16::from_be_bytes([first[0], first[1]]), u16::from_be_bytes([first[2], first[3]])));
+	}
+
+	while points.len() < count {
+		let (x, y) = points[points.len() - 1];
+		let x = u16::try_from(x as i32 + read_delta(stream)?).map_err(|_| ProtocolError::InvalidPolyline)?;
+		let y = u16::try_from(y as i32 + read_delta(stream)?).map_err(|_| ProtocolError::InvalidPolyline)?;
+		points.push((x, y));
+	}
+
+	Ok(points)
+}
+
+fn read_delta<R: Read>(stream: &mut R) -> Result<i32, ProtocolError> {
+	let mut zigzag = 0u32;
+	for i in 0..MAX_DELTA_LEN {
+		let mut byte = [0u8; 1];
+		stream.read_exact(&mut byte)?;
+		zigzag |= ((byte[0] & 0x7f) as u32) << (7 * i);
+		if byte[0] & 0x80 == 0 {
+			return Ok((zigzag >> 1) as i32 ^ -((zigzag & 1) as i32));
+		}
+	}
+
+	Err(ProtocolError::InvalidPolyline)
+}
+
+fn read_list_len(len: u32) -> Result<u32, ProtocolError> {
+	if len as usize > MAX_LIST_LEN {
+		return Err(ProtocolError::ListTooLong(len as usize));
+	}
+
+	Ok(len)
+}
+
+fn read_string<R: Read>(len: usize, stream: &mut R) -> Result<String, ProtocolError> {
+	if len > MAX_STRING_LEN {
+		return Err(ProtocolError::StringTooLong(len));
+	}
+
+	let mut bytes = vec![0; len];
+	stream.read_exact(&mut bytes[..len])?;
+
+	String::from_utf8(bytes).map_err(|_| ProtocolError::InvalidUtf8)
+}
+
+/// Packs RGBA pixels into runs of identical pixels.
+fn encode_runs(pixels: &[u8]) -> Vec<u8> {
+	let mut runs: Vec<u8> = Vec::new();
+	let mut pixels = pixels.chunks_exact(RGBA).peekable();
+	while let Some(pixel) = pixels.next() {
+		let mut len = 1;
+		while len < MAX_RUN_LEN && pixels.next_if_eq(&pixel).is_some() {
+			len += 1;
+		}
+
+		runs.push(len as u8);
+		runs.extend_from_slice(pixel);
+	}
+
+	runs
+}
+
+/// Expands runs back into exactly `pixels` RGBA pixels.
+fn decode_runs(runs: &[u8], pixels: usize) -> Result<Vec<u8>, ProtocolError> {
+	let mut decoded = Vec::with_capacity(pixels * RGBA);
+	for run in runs.chunks_exact(RUN_SIZE) {
+		if decoded.len() + run[0] as usize * RGBA > pixels * RGBA {
+			return Err(ProtocolError::InvalidSnapshot);
+		}
+
+		for _ in 0..run[0] {
+			decoded.extend_from_slice(&run[1..]);
+		}
+	}
+
+	if decoded.len() != pixels * RGBA {
+		return Err(ProtocolError::InvalidSnapshot);
+	}
+
+	Ok(decoded)
+}
+
+fn color_from_bytes(bytes: &[u8]) -> Color {
+	Color::new(bytes[0], bytes[1], bytes[2])
+}
+
+fn brush_from_bytes(bytes: &[u8]) -> Result<Brush, ProtocolError> {
+	let shape = match bytes[1] {
+		0 => BrushShape::Round,
+		_ => BrushShape::Square,
+	};
+
+	if bytes[0] == 0 || bytes[0] > Brush::MAX_SIZE {
+		return Err(ProtocolError::InvalidBrush(bytes[0]));
+	}
+
+	Ok(Brush::new(bytes[0], shape)
+		.with_antialiasing(bytes[2] & 1 != 0)
+		.with_smoothing(bytes[2] & 2 != 0))
+}
+
+fn u32_to_bytes(x: u32) -> [u8; 4] {
+    [
+		((x >> 24) & 0xff) as u8,
+		((x >> 16) & 0xff) as u8,
+		((x >> 8) & 0xff) as u8,
+		(x & 0xff) as u8
+	]
+}
+
+fn u32_from_bytes(bytes: &[u8]) -> u32 {
+	let mut x: u32 = 0;
+	x |= (bytes[0] as u32) << 24;
+	x |= (bytes[1] as u32) << 16;
+	x |= (bytes[2] as u32) << 8;
+	x |= bytes[3] as u32;
+	x
+}
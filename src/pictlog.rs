@@ -0,0 +1,159 @@
+//! `.pictlog` recordings of a session as one player saw it: every message the server sent them
+//! plus the strokes they drew themselves and the words they picked to draw, each stamped with
+//! when it happened. A recording is played back by serving it to an ordinary client as if it
+//! were the server; the client only watches it, like a spectator.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::message::{CAPABILITIES, GameMessage, PROTOCOL_VERSION, parse_game_message};
+
+/// Starts every recording, followed by the protocol version its messages are encoded with and
+/// the size of the canvas they were drawn on.
+const MAGIC: &[u8; 8] = b"PICTLOG\0";
+
+/// The name the playback introduces itself with in its `Hello`.
+const REPLAY_NAME: &str = "pictionary-replay";
+
+/// A recorded message: when it arrived and its wire encoding.
+pub struct Entry {
+	pub time: Duration,
+	pub bytes: Vec<u8>,
+}
+
+/// A whole recording, as read back from its file.
+pub struct Recording {
+	pub canvas_size: u32,
+	pub entries: Vec<Entry>,
+}
+
+/// Appends messages to a recording as the session goes on.
+pub struct Recorder {
+	file: BufWriter<File>,
+	start: Instant,
+}
+
+impl Recorder {
+	pub fn create(path: &Path, canvas_size: u32) -> io::Result<Self> {
+		let mut file = BufWriter::new(File::create(path)?);
+		file.write_all(MAGIC)?;
+		file.write_all(&PROTOCOL_VERSION.to_be_bytes())?;
+		file.write_all(&canvas_size.to_be_bytes())?;
+		file.flush()?;
+
+		Ok(Recorder {
+			file,
+			start: Instant::now(),
+		})
+	}
+
+	/// Records a message from the server.
+	pub fn received(&mut self, message: &GameMessage) -> io::Result<()> {
+		let time = self.start.elapsed().as_millis() as u64;
+		let bytes = message.encode();
+		self.file.write_all(&time.to_be_bytes())?;
+		self.file.write_all(&(bytes.len() as u32).to_be_bytes())?;
+		self.file.write_all(&bytes)?;
+		// The window can be closed at any moment, so nothing is left sitting in the buffer.
+		self.file.flush()
+	}
+
+	/// Records a message sent to the server, if it is a stroke. The server does not echo
+	/// strokes back to the drawer, and everything else it answers in messages of its own.
+	pub fn sent(&mut self, message: &GameMessage) -> io::Result<()> {
+		match message {
+			GameMessage::Draw(..) | GameMessage::DrawLine(..) |
+			GameMessage::Erase(..) | GameMessage::EraseLine(..) |
+			GameMessage::Fill(..) | GameMessage::Undo | GameMessage::Redo |
+			GameMessage::Polyline { .. } | GameMessage::ErasePolyline { .. } => self.received(message),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Reads a recording made with this protocol version.
+pub fn read(path: &Path) -> Result<Recording, Box<dyn Error>> {
+	let contents = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+	let invalid = || format!("{} is not a valid recording", path.display());
+
+	let header = MAGIC.len() + 8;
+	if contents.len() < header || &contents[..MAGIC.len()] != MAGIC {
+		return Err(invalid().into());
+	}
+
+	let version = u32::from_be_bytes(contents[MAGIC.len()..MAGIC.len() + 4].try_into()?);
+	if version != PROTOCOL_VERSION {
+		return Err(format!("{} was recorded with protocol version {}, this is version {}", path.display(), version, PROTOCOL_VERSION).into());
+	}
+
+	let canvas_size = u32::from_be_bytes(contents[MAGIC.len() + 4..header].try_into()?);
+
+	let mut entries = Vec::new();
+	let mut rest = &contents[header..];
+	while !rest.is_empty() {
+		if rest.len() < 12 {
+			return Err(invalid().into());
+		}
+
+		let time = u64::from_be_bytes(rest[0..8].try_into()?);
+		let len = u32::from_be_bytes(rest[8..12].try_into()?) as usize;
+		let bytes = rest.get(12..12 + len).ok_or_else(invalid)?;
+		entries.push(Entry {
+			time: Duration::from_millis(time),
+			bytes: bytes.to_vec(),
+		});
+		rest = &rest[12 + len..];
+	}
+
+	Ok(Recording { canvas_size, entries })
+}
+
+/// Serves the recording on a local port to the first client that connects, `speed` times as
+/// fast as it was recorded, and returns the address to connect to. The connection is kept
+/// open once the recording runs out so the last picture stays on screen.
+pub fn serve(path: &Path, speed: f64) -> Result<SocketAddr, Box<dyn Error>> {
+	let recording = read(path)?;
+	let listener = TcpListener::bind((IpAddr::V4(Ipv4Addr::LOCALHOST), 0))?;
+	let address = listener.local_addr()?;
+
+	thread::spawn(move || {
+		if let Ok((stream, _)) = listener.accept() {
+			let _ = play(stream, recording, speed);
+		}
+	});
+
+	Ok(address)
+}
+
+fn play(mut stream: TcpStream, recording: Recording, speed: f64) -> io::Result<()> {
+	if !matches!(parse_game_message(&mut stream), Ok(GameMessage::Hello { .. })) {
+		return Ok(());
+	}
+
+	GameMessage::Hello {
+		protocol_version: PROTOCOL_VERSION,
+		client_name: REPLAY_NAME.to_owned(),
+		capabilities: CAPABILITIES,
+		canvas_size: recording.canvas_size,
+		spectating: false,
+	}.send(&stream)?;
+
+	let start = Instant::now();
+	for entry in recording.entries {
+		let due = entry.time.div_f64(speed);
+		if let Some(wait) = due.checked_sub(start.elapsed()) {
+			thread::sleep(wait);
+		}
+
+		(&stream).write_all(&entry.bytes)?;
+	}
+
+	// Whatever the player does during playback goes nowhere.
+	while parse_game_message(&mut stream).is_ok() {}
+	Ok(())
+}
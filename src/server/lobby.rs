@@ -0,0 +1,113 @@
+use std::sync::{Arc, Mutex};
+
+use crate::message::{Capabilities, GameMessage};
+use crate::transport::Transport;
+
+struct Peer {
+	id: u32,
+	transport: Box<dyn Transport>,
+	capabilities: Capabilities,
+}
+
+impl Peer {
+	/// Writes the message unless the peer never negotiated the capability it needs. A peer
+	/// that cannot read polylines is sent their lines one by one instead.
+	fn send(&self, message: &GameMessage) {
+		let required = message.required_capabilities();
+		if self.capabilities & required == required {
+			let _ = self.transport.send(message);
+		} else {
+			for line in message.lines() {
+				let _ = self.transport.send(&line);
+			}
+		}
+	}
+}
+
+struct Peers {
+	next_id: u32,
+	connected: Vec<Peer>,
+	/// Ids kept for players who dropped, by name, so they get them back when they reconnect.
+	held: Vec<(u32, String)>,
+}
+
+/// The set of open client connections, shared between the server loop and the reader threads.
+#[derive(Clone)]
+pub struct Lobby {
+	peers: Arc<Mutex<Peers>>,
+}
+
+impl Lobby {
+	pub fn new() -> Self {
+		Lobby {
+			peers: Arc::new(Mutex::new(Peers {
+				next_id: 1,
+				connected: Vec::new(),
+				held: Vec::new(),
+			})),
+		}
+	}
+
+	/// Registers a client connection and returns the player id it was assigned: the id held
+	/// for `name` if that player dropped recently, otherwise a new one.
+	pub fn add(&self, transport: Box<dyn Transport>, capabilities: Capabilities, name: &str) -> u32 {
+		let mut peers = self.peers.lock().unwrap();
+		let id = match peers.held.iter().position(|(_, held)| held == name) {
+			Some(i) => peers.held.remove(i).0,
+			None => {
+				peers.next_id += 1;
+				peers.next_id - 1
+			}
+		};
+
+		peers.connected.push(Peer { id, transport, capabilities });
+		id
+	}
+
+	/// Registers a spectator's connection under a fresh id; spectators never take a held one.
+	pub fn add_spectator(&self, transport: Box<dyn Transport>, capabilities: Capabilities) -> u32 {
+		let mut peers = self.peers.lock().unwrap();
+		let id = peers.next_id;
+		peers.next_id += 1;
+		peers.connected.push(Peer { id, transport, capabilities });
+		id
+	}
+
+	/// Keeps `id` for the next client to connect as `name`.
+	pub fn hold(&self, id: u32, name: String) {
+		self.peers.lock().unwrap().held.push((id, name));
+	}
+
+	pub fn release(&self, id: u32) {
+		self.peers.lock().unwrap().held.retain(|(held, _)| *held != id);
+	}
+
+	/// Shuts every connection down; each reader thread then reports its client as left.
+	pub fn close_all(&self) {
+		for peer in self.peers.lock().unwrap().connected.iter() {
+			peer.transport.close();
+		}
+	}
+
+	pub fn remove(&self, id: u32) {
+		self.peers.lock().unwrap().connected.retain(|peer| peer.id != id);
+	}
+
+	/// Sends a message to every client except `except`. Failed writes are ignored here;
+	/// the client's reader thread notices the broken connection and reports the leave.
+	pub fn broadcast(&self, message: &GameMessage, except: Option<u32>) {
+		for peer in self.peers.lock().unwrap().connected.iter() {
+			if Some(peer.id) != except {
+				peer.send(message);
+			}
+		}
+	}
+
+	pub fn send_to(&self, id: u32, message: &GameMessage) {
+		for peer in self.peers.lock().unwrap().connected.iter() {
+			if peer.id == id {
+				peer.send(message);
+			}
+		}
+	}
+}
@@ -0,0 +1,195 @@
+mod lobby;
+mod session;
+pub mod words;
+
+use std::error::Error;
+use std::io::{self, Read};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Settings;
+use crate::message::{CAPABILITIES, GameMessage, PROTOCOL_VERSION, ProtocolError, parse_game_message};
+use crate::transport::{LocalStream, Transport, local_pair};
+use lobby::Lobby;
+use session::Session;
+use words::WordList;
+
+/// The name the server introduces itself with in its `Hello`.
+const SERVER_NAME: &str = "pictionary-server";
+
+/// Something that happened on one of the server's connections or timers.
+enum ServerEvent {
+	Joined(u32, String),
+	/// A client that only watches: it is sent the game but never takes part in it.
+	Spectating(u32, String),
+	Message(u32, GameMessage),
+	Left(u32),
+	Tick,
+}
+
+/// The authoritative game: it picks the words, runs the timer, checks guesses and rotates
+/// the drawer, while every client (including a hosting window) connects as an equal peer.
+pub struct Server {
+	listener: Option<TcpListener>,
+	/// The server's ends of the in-process connections handed out by `connect_local`.
+	local: Vec<LocalStream>,
+	words: WordList,
+	settings: Settings,
+	/// How long a second of the game's clock lasts.
+	tick: Duration,
+}
+
+impl Server {
+	pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+		Ok(Server {
+			listener: Some(TcpListener::bind(address)?),
+			..Server::local()
+		})
+	}
+
+	/// A server that only takes in-process connections, for players sharing one window.
+	pub fn local() -> Self {
+		Server {
+			listener: None,
+			local: Vec::new(),
+			words: WordList::default(),
+			settings: Settings::default(),
+			tick: Duration::from_secs(1),
+		}
+	}
+
+	/// Opens an in-process connection and returns the client's end. The server starts
+	/// serving it once it runs.
+	pub fn connect_local(&mut self) -> LocalStream {
+		let (client, server) = local_pair();
+		self.local.push(server);
+		client
+	}
+
+	/// Applies the host's settings, loading the word packs they name.
+	pub fn configure(self, settings: Settings) -> Result<Self, Box<dyn Error>> {
+		let words = WordList::select(&settings.words, settings.difficulty)?;
+		Ok(self.with_words(words).with_settings(settings))
+	}
+
+	/// Replaces the built-in words the drawer chooses from.
+	pub fn with_words(mut self, words: WordList) -> Self {
+		self.words = words;
+		self
+	}
+
+	/// Replaces the round settings without touching the words.
+	pub fn with_settings(mut self, settings: Settings) -> Self {
+		self.settings = settings;
+		self
+	}
+
+	/// Makes each second of the game's clock last `tick` instead, so minutes of it can pass
+	/// quickly in a test.
+	pub fn with_tick(mut self, tick: Duration) -> Self {
+		self.tick = tick;
+		self
+	}
+
+	pub fn local_addr(&self) -> io::Result<SocketAddr> {
+		match &self.listener {
+			Some(listener) => listener.local_addr(),
+			None => Err(io::Error::new(io::ErrorKind::NotConnected, "the server only takes local connections")),
+		}
+	}
+
+	/// Serves clients for as long as the process runs.
+	pub fn run(self) {
+		let lobby = Lobby::new();
+		let (sender, receiver) = channel();
+
+		let (tick_sender, tick) = (sender.clone(), self.tick);
+		thread::spawn(move || {
+			loop {
+				thread::sleep(tick);
+				tick_sender.send(ServerEvent::Tick).unwrap();
+			}
+		});
+
+		let canvas_size = self.settings.canvas_size;
+		for stream in self.local {
+			let (reader, writer) = stream.split();
+			let (lobby, event_sender) = (lobby.clone(), sender.clone());
+			thread::spawn(move || Server::serve_client(reader, Box::new(writer), lobby, event_sender, canvas_size));
+		}
+
+		if let Some(listener) = self.listener {
+			let accept_lobby = lobby.clone();
+			thread::spawn(move || {
+				for stream in listener.incoming().flatten() {
+					let reader = match stream.try_clone() {
+						Ok(reader) => reader,
+						Err(_) => continue,
+					};
+
+					let lobby = accept_lobby.clone();
+					let event_sender = sender.clone();
+					thread::spawn(move || Server::serve_client(reader, Box::new(stream), lobby, event_sender, canvas_size));
+				}
+			});
+		}
+
+		let mut session = Session::new(lobby, self.words, self.settings);
+		for event in receiver {
+			session.process_event(event);
+		}
+	}
+
+	fn serve_client<R: Read>(mut reader: R, transport: Box<dyn Transport>, lobby: Lobby, event_sender: Sender<ServerEvent>, canvas_size: u32) {
+		let (name, capabilities, spectating) = match parse_game_message(&mut reader) {
+			Ok(GameMessage::Hello { protocol_version, client_name, capabilities, spectating, .. }) => {
+				if protocol_version != PROTOCOL_VERSION {
+					let reason = format!("Server speaks protocol version {}, client speaks {}", PROTOCOL_VERSION, protocol_version);
+					let _ = transport.send(&GameMessage::Reject(reason));
+					return;
+				}
+
+				(client_name, capabilities & CAPABILITIES, spectating)
+			},
+
+			_ => return,
+		};
+
+		let hello = GameMessage::Hello {
+			protocol_version: PROTOCOL_VERSION,
+			client_name: SERVER_NAME.to_owned(),
+			capabilities: CAPABILITIES,
+			canvas_size,
+			spectating: false,
+		};
+
+		if transport.send(&hello).is_err() {
+			return;
+		}
+
+		let (id, event) = if spectating {
+			let id = lobby.add_spectator(transport, capabilities);
+			(id, ServerEvent::Spectating(id, name))
+		} else {
+			let id = lobby.add(transport, capabilities, &name);
+			(id, ServerEvent::Joined(id, name))
+		};
+		event_sender.send(event).unwrap();
+
+		loop {
+			match parse_game_message(&mut reader) {
+				Ok(message) => event_sender.send(ServerEvent::Message(id, message)).unwrap(),
+				Err(ProtocolError::Eof) => break,
+				Err(e) => {
+					eprintln!("Dropping player {}: {}", id, e);
+					break;
+				}
+			}
+		}
+
+		lobby.remove(id);
+		event_sender.send(ServerEvent::Left(id)).unwrap();
+	}
+}
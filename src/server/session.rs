@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+
+use crate::canvas::{CanvasOperation, Picture};
+use crate::message::{GameMessage, RECONNECT_SECONDS, RoundResult, Verdict};
+use crate::config::Settings;
+use crate::server::{ServerEvent, lobby::Lobby, words::WordList};
+
+/// A correct guess is worth the minimum plus the rest scaled by the share of time left.
+const GUESSER_MIN_POINTS: u32 = 10;
+const GUESSER_MAX_POINTS: u32 = 100;
+
+/// Awarded to the drawer whenever their word is guessed.
+const DRAWER_POINTS: u32 = 50;
+
+/// How long a dropped player's place and score are kept for them to reconnect: as long as the
+/// client keeps trying, with time left for its last attempt to get through.
+const AWAY_SECONDS: u32 = RECONNECT_SECONDS + HANDSHAKE_SECONDS;
+const HANDSHAKE_SECONDS: u32 = 15;
+
+/// How often guessers are sent the whole picture while a word is being drawn, in case theirs
+/// has drifted from the drawer's.
+const SNAPSHOT_SECONDS: u32 = 10;
+
+enum Round {
+	Waiting,
+	PickingWord(Vec<String>),
+	/// The word, the seconds left and the skeleton with any hint letters revealed.
+	Drawing(String, u32, String),
+	Done,
+}
+
+/// Server-side state of the match: who is playing, who draws, and how far the round has got.
+pub struct Session {
+	lobby: Lobby,
+	words: WordList,
+	settings: Settings,
+	players: Vec<(u32, String)>,
+	/// Players whose connection dropped, with the seconds they have left to come back.
+	away: Vec<(u32, String, u32)>,
+	/// Clients watching the game. They are sent everything the guessers are, and the answer,
+	/// but never draw, guess or score.
+	spectators: Vec<(u32, String)>,
+	scores: HashMap<u32, u32>,
+	results: Vec<RoundResult>,
+	drawer_id: Option<u32>,
+	round: Round,
+	/// The drawer's picture as the guessers should see it, sent to anyone who joins while it
+	/// is on screen.
+	picture: Picture,
+	/// Whether anyone has been sent a snapshot of this round's picture. Their undo history
+	/// starts at the snapshot, so from then on every undo or redo is followed by a fresh one.
+	resynced: bool,
+}
+
+fn blank_picture(size: u32) -> Picture {
+	let mut picture = Picture::new(size, size);
+	picture.apply(CanvasOperation::Clear);
+	picture
+}
+
+/// What a drawing message does to the picture.
+fn canvas_operation(message: &GameMessage) -> Option<CanvasOperation> {
+	Some(match *message {
+		GameMessage::Draw(x, y, color, brush) => CanvasOperation::Pixel(x, y, color, brush),
+		GameMessage::DrawLine(x1, y1, x2, y2, color, brush) => CanvasOperation::Line(x1, y1, x2, y2, color, brush),
+		GameMessage::Erase(x, y, brush) => CanvasOperation::Erase(x, y, brush),
+		GameMessage::EraseLine(x1, y1, x2, y2, brush) => CanvasOperation::EraseLine(x1, y1, x2, y2, brush),
+		GameMessage::Fill(x, y, color) => CanvasOperation::Fill(x, y, color),
+		GameMessage::Undo => CanvasOperation::Undo,
+		GameMessage::Redo => CanvasOperation::Redo,
+		_ => return None,
+	})
+}
+
+fn snapshot(picture: &Picture) -> GameMessage {
+	let image = picture.image();
+	GameMessage::CanvasSnapshot(image.width(), image.height(), image.as_raw().clone())
+}
+
+fn skeleton(word: &str) -> String {
+	word.chars().map(|c| {
+		match c {
+			' ' => ' ',
+			_ => '_'
+		}
+	}).collect()
+}
+
+/// Lowercases the text and collapses every run of whitespace into a single space.
+fn normalize(text: &str) -> String {
+	text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Levenshtein distance: the fewest single character insertions, deletions or substitutions
+/// that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+	for (i, a) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b) in b.iter().enumerate() {
+			let substitution = previous[j] + if a == *b { 0 } else { 1 };
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+
+	previous[b.len()]
+}
+
+/// The most edits a wrong guess may be from `answer` and still count as close. A near miss at
+/// a short word gives most of it away, so the shortest have none.
+fn close_guess_distance(answer: &str) -> usize {
+	match answer.chars().filter(|c| !c.is_whitespace()).count() {
+		0..=3 => 0,
+		4..=6 => 1,
+		_ => 2,
+	}
+}
+
+/// Reveals random letters in the skeleton until `count` are showing, always keeping at least
+/// one hidden. Returns whether anything was revealed.
+fn reveal_letters(word: &str, skeleton: &mut String, count: usize) -> bool {
+	let word: Vec<char> = word.chars().collect();
+	let mut letters: Vec<char> = skeleton.chars().collect();
+	let hidden: Vec<usize> = (0..letters.len()).filter(|i| letters[*i] == '_').collect();
+	let revealed = letters.iter().filter(|c| **c != '_' && **c != ' ').count();
+
+	let reveal = count.saturating_sub(revealed).min(hidden.len().saturating_sub(1));
+	if reveal == 0 {
+		return false;
+	}
+
+	for i in hidden.choose_multiple(&mut rand::thread_rng(), reveal) {
+		letters[*i] = word[*i];
+	}
+
+	*skeleton = letters.into_iter().collect();
+	true
+}
+
+impl Session {
+	pub fn new(lobby: Lobby, words: WordList, settings: Settings) -> Self {
+		let picture = blank_picture(settings.canvas_size);
+		Session {
+			lobby,
+			words,
+			settings,
+			players: Vec::new(),
+			away: Vec::new(),
+			spectators: Vec::new(),
+			scores: HashMap::new(),
+			results: Vec::new(),
+			drawer_id: None,
+			round: Round::Waiting,
+			picture,
+			resynced: false,
+		}
+	}
+
+	pub fn process_event(&mut self, event: ServerEvent) {
+		match event {
+			ServerEvent::Joined(id, name) => self.join(id, name),
+			ServerEvent::Spectating(id, name) => self.spectate(id, name),
+			ServerEvent::Left(id) => self.leave(id),
+			ServerEvent::Message(id, message) => self.process_message(id, message),
+			ServerEvent::Tick => self.tick(),
+		}
+	}
+
+	fn join(&mut self, id: u32, name: String) {
+		// The lobby hands a reconnecting player their old id, and with it their score.
+		self.away.retain(|(away_id, ..)| *away_id != id);
+		let name = if name.is_empty() { format!("Player {}", id) } else { name };
+		self.players.push((id, name));
+		self.send_settings(id);
+		self.send_roster();
+		if self.scores.values().any(|score| *score > 0) {
+			self.lobby.send_to(id, &self.score_update());
+		}
+
+		match self.drawer_id {
+			Some(drawer_id) => self.catch_up(id, drawer_id),
+			None => {
+				if self.players.len() >= 2 {
+					self.start_round(self.players[0].0);
+				}
+			}
+		}
+	}
+
+	/// Lets a client watch without joining the rotation; it is told the answer along with
+	/// everything a guesser sees.
+	fn spectate(&mut self, id: u32, name: String) {
+		self.spectators.push((id, name));
+		self.send_settings(id);
+		self.lobby.send_to(id, &GameMessage::Roster(id, self.players.clone()));
+		if self.scores.values().any(|score| *score > 0) {
+			self.lobby.send_to(id, &self.score_update());
+		}
+
+		if let Some(drawer_id) = self.drawer_id {
+			self.catch_up(id, drawer_id);
+		}
+	}
+
+	fn send_settings(&self, id: u32) {
+		self.lobby.send_to(id, &GameMessage::Settings {
+			round_seconds: self.settings.round_seconds,
+			choices: self.settings.choices,
+			rounds: self.settings.rounds,
+		});
+	}
+
+	/// Shows a client who joined mid-round who is drawing and what is on the canvas.
+	fn catch_up(&mut self, id: u32, drawer_id: u32) {
+		self.lobby.send_to(id, &GameMessage::SwapRoles(drawer_id));
+		match &self.round {
+			Round::Drawing(word, time, skeleton) => {
+				self.lobby.send_to(id, &GameMessage::SetWordSkeleton(skeleton.clone()));
+				self.lobby.send_to(id, &GameMessage::SetTimeRemaining(*time));
+				if self.is_spectator(id) {
+					self.lobby.send_to(id, &GameMessage::Answer(word.clone()));
+				}
+				self.lobby.send_to(id, &snapshot(&self.picture));
+				self.resynced = true;
+			},
+
+			Round::Done => self.lobby.send_to(id, &snapshot(&self.picture)),
+			Round::Waiting | Round::PickingWord(_) => {},
+		}
+	}
+
+	/// Takes the player out of the game, keeping their place and score for a while in case they
+	/// reconnect. The round carries on without them unless they were drawing.
+	fn leave(&mut self, id: u32) {
+		if self.is_spectator(id) {
+			self.spectators.retain(|(spectator_id, _)| *spectator_id != id);
+			return;
+		}
+
+		let next_drawer_id = self.next_drawer_id();
+		let name = self.name(id);
+		self.players.retain(|(player_id, _)| *player_id != id);
+		self.away.push((id, name.clone(), AWAY_SECONDS));
+		self.lobby.hold(id, name);
+		self.lobby.broadcast(&GameMessage::Leave(id), None);
+		self.send_roster();
+
+		if self.players.is_empty() {
+			self.results.clear();
+		}
+
+		if self.drawer_id == Some(id) && self.players.len() >= 2 {
+			self.start_round(next_drawer_id);
+		} else if self.drawer_id == Some(id) || self.players.len() + self.away.len() < 2 {
+			self.stop_round();
+		}
+	}
+
+	fn process_message(&mut self, id: u32, message: GameMessage) {
+		if self.is_spectator(id) {
+			return;
+		}
+
+		let is_drawer = self.drawer_id == Some(id);
+
+		match (&self.round, message) {
+			(Round::PickingWord(words), GameMessage::PickWord(index)) if is_drawer => {
+				if let Some(word) = words.get(index as usize).cloned() {
+					let skeleton = skeleton(&word);
+					self.picture = blank_picture(self.settings.canvas_size);
+					self.resynced = false;
+					self.lobby.broadcast(&GameMessage::SetWordSkeleton(skeleton.clone()), Some(id));
+					for (spectator_id, _) in self.spectators.iter() {
+						self.lobby.send_to(*spectator_id, &GameMessage::Answer(word.clone()));
+					}
+					self.round = Round::Drawing(word, self.settings.round_seconds, skeleton);
+				}
+			},
+
+			(Round::Drawing(..), message @ (
+				GameMessage::Draw(..) | GameMessage::DrawLine(..) |
+				GameMessage::Erase(..) | GameMessage::EraseLine(..) |
+				GameMessage::Fill(..) | GameMessage::Undo | GameMessage::Redo |
+				GameMessage::Polyline { .. } | GameMessage::ErasePolyline { .. }
+			)) if is_drawer && message.in_bounds(self.settings.canvas_size) => {
+				self.lobby.broadcast(&message, Some(id));
+				if let Some(operation) = canvas_operation(&message) {
+					self.picture.apply(operation);
+				}
+
+				for line in message.lines() {
+					if let Some(operation) = canvas_operation(&line) {
+						self.picture.apply(operation);
+					}
+				}
+
+				if self.resynced && matches!(message, GameMessage::Undo | GameMessage::Redo) {
+					self.lobby.broadcast(&snapshot(&self.picture), Some(id));
+				}
+			},
+
+			(Round::Drawing(word, time, _), GameMessage::Guess(_, guess)) if !is_drawer => {
+				let (guess, answer) = (normalize(&guess), normalize(word));
+				if guess.is_empty() {
+					return;
+				}
+
+				if guess == answer {
+					self.lobby.broadcast(&GameMessage::GuessResult(id, Verdict::Correct(word.clone())), None);
+					// In u64, since a long enough round would overflow the product.
+					let bonus = (GUESSER_MAX_POINTS - GUESSER_MIN_POINTS) as u64 * *time as u64 / self.settings.round_seconds as u64;
+					let points = GUESSER_MIN_POINTS + bonus as u32;
+					*self.scores.entry(id).or_default() += points;
+					if let Some(drawer_id) = self.drawer_id {
+						*self.scores.entry(drawer_id).or_default() += DRAWER_POINTS;
+					}
+
+					self.lobby.broadcast(&self.score_update(), None);
+					let result = RoundResult {
+						word: word.clone(),
+						winner: Some(self.name(id)),
+						seconds: self.settings.round_seconds - time,
+					};
+					self.finish_round(result);
+				} else if edit_distance(&guess, &answer) <= close_guess_distance(&answer) {
+					// Other guessers would learn too much from a near miss, so it stays between
+					// the guesser, the drawer and anyone watching.
+					let watchers = self.spectators.iter().map(|(spectator_id, _)| *spectator_id);
+					for player in [Some(id), self.drawer_id].into_iter().flatten().chain(watchers) {
+						self.lobby.send_to(player, &GameMessage::Guess(id, guess.clone()));
+						self.lobby.send_to(player, &GameMessage::GuessResult(id, Verdict::Close));
+					}
+				} else {
+					self.lobby.broadcast(&GameMessage::Guess(id, guess), None);
+					self.lobby.send_to(id, &GameMessage::GuessResult(id, Verdict::Wrong));
+				}
+			},
+
+			(Round::Done, GameMessage::NextRound) if is_drawer => {
+				self.start_round(self.next_drawer_id());
+			},
+
+			_ => {}
+		}
+	}
+
+	fn tick(&mut self) {
+		for (_, _, time) in self.away.iter_mut() {
+			*time = time.saturating_sub(1);
+		}
+
+		let (expired, away): (Vec<_>, Vec<_>) = self.away.drain(..).partition(|(_, _, time)| *time == 0);
+		self.away = away;
+		for (id, ..) in expired {
+			self.scores.remove(&id);
+			self.lobby.release(id);
+			if self.players.len() + self.away.len() < 2 {
+				self.stop_round();
+			}
+		}
+
+		if let Round::Drawing(word, time, skeleton) = &mut self.round {
+			*time = time.saturating_sub(1);
+			self.lobby.broadcast(&GameMessage::SetTimeRemaining(*time), None);
+
+			let round_seconds = self.settings.round_seconds;
+			let elapsed = round_seconds - *time;
+			let due = self.settings.hints.iter()
+				.filter(|percent| elapsed as u64 * 100 >= round_seconds as u64 * **percent as u64)
+				.count();
+			if reveal_letters(word, skeleton, due) {
+				self.lobby.broadcast(&GameMessage::SetWordSkeleton(skeleton.clone()), self.drawer_id);
+			}
+
+			if *time % SNAPSHOT_SECONDS == 0 {
+				self.lobby.broadcast(&snapshot(&self.picture), self.drawer_id);
+				self.resynced = true;
+			}
+
+			if *time == 0 {
+				self.lobby.broadcast(&GameMessage::GameOver(word.clone()), None);
+				let result = RoundResult {
+					word: word.clone(),
+					winner: None,
+					seconds: round_seconds,
+				};
+				self.finish_round(result);
+			}
+		}
+	}
+
+	/// Records the round and, once the match has had all its rounds, sends everyone the
+	/// results and closes their connections.
+	fn finish_round(&mut self, result: RoundResult) {
+		self.results.push(result);
+		self.round = Round::Done;
+
+		if self.settings.rounds > 0 && self.results.len() >= self.settings.rounds as usize {
+			self.lobby.broadcast(&GameMessage::MatchOver(self.results.clone()), None);
+			self.lobby.close_all();
+			self.results.clear();
+			self.scores.clear();
+		}
+	}
+
+	fn start_round(&mut self, drawer_id: u32) {
+		let words = self.words.choose(self.settings.choices as usize);
+
+		self.drawer_id = Some(drawer_id);
+		self.lobby.broadcast(&GameMessage::SwapRoles(drawer_id), None);
+		self.lobby.send_to(drawer_id, &GameMessage::WordChoices(words.clone()));
+		self.round = Round::PickingWord(words);
+	}
+
+	/// Waits for enough players to start another round.
+	fn stop_round(&mut self) {
+		self.drawer_id = None;
+		self.round = Round::Waiting;
+		self.picture = blank_picture(self.settings.canvas_size);
+	}
+
+	/// The player after the current drawer in join order, wrapping around to the first.
+	fn next_drawer_id(&self) -> u32 {
+		let current = self.drawer_id
+			.and_then(|drawer| self.players.iter().position(|(id, _)| *id == drawer));
+
+		match current {
+			Some(i) => self.players[(i + 1) % self.players.len()].0,
+			None => self.players[0].0,
+		}
+	}
+
+	fn is_spectator(&self, id: u32) -> bool {
+		self.spectators.iter().any(|(spectator_id, _)| *spectator_id == id)
+	}
+
+	fn name(&self, id: u32) -> String {
+		self.players.iter()
+			.find(|(player_id, _)| *player_id == id)
+			.map_or_else(String::new, |(_, name)| name.clone())
+	}
+
+	/// Every player's total in join order.
+	fn score_update(&self) -> GameMessage {
+		GameMessage::ScoreUpdate(self.players.iter()
+			.map(|(id, _)| (*id, self.scores.get(id).copied().unwrap_or(0)))
+			.collect())
+	}
+
+	/// Sends every client the current player list along with its own id.
+	fn send_roster(&self) {
+		for (id, _) in self.players.iter().chain(self.spectators.iter()) {
+			self.lobby.send_to(*id, &GameMessage::Roster(*id, self.players.clone()));
+		}
+	}
+}
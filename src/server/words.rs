@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+/// Used when no word packs can be found, e.g. for a server started outside the repository.
+const DEFAULT_WORDS: &[&str] = &[
+	"bike", "snowman", "tree", "flower", "basketball",
+	"mountain", "turtle", "book",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+	Easy,
+	#[default]
+	Medium,
+	Hard,
+}
+
+impl FromStr for Difficulty {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"easy" => Ok(Difficulty::Easy),
+			"medium" => Ok(Difficulty::Medium),
+			"hard" => Ok(Difficulty::Hard),
+			_ => Err(format!("Unknown difficulty '{}' (expected easy, medium or hard)", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+	pub text: String,
+	pub category: String,
+	pub difficulty: Difficulty,
+}
+
+/// A `.toml` pack: pack-wide defaults followed by the words, each either a plain string or a
+/// table overriding the defaults.
+#[derive(Deserialize)]
+struct TomlPack {
+	category: Option<String>,
+	#[serde(default)]
+	difficulty: Difficulty,
+	words: Vec<TomlWord>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlWord {
+	Plain(String),
+	Detailed {
+		word: String,
+		category: Option<String>,
+		difficulty: Option<Difficulty>,
+	},
+}
+
+/// Reads a pack file. A `.txt` pack has one word per line (`#` starts a comment) and takes
+/// its category from the file name; a `.toml` pack can set category and difficulty per word.
+pub fn load_pack(path: &Path) -> Result<Vec<Word>, Box<dyn Error>> {
+	let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_owned();
+	let contents = fs::read_to_string(path)?;
+
+	let words = if path.extension().is_some_and(|ext| ext == "toml") {
+		let pack: TomlPack = toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+		let category = pack.category.unwrap_or(name);
+
+		pack.words.into_iter().map(|word| match word {
+			TomlWord::Plain(text) => Word {
+				text,
+				category: category.clone(),
+				difficulty: pack.difficulty,
+			},
+
+			TomlWord::Detailed { word, category: word_category, difficulty } => Word {
+				text: word,
+				category: word_category.unwrap_or_else(|| category.clone()),
+				difficulty: difficulty.unwrap_or(pack.difficulty),
+			},
+		}).collect()
+	} else {
+		contents.lines()
+			.map(|line| line.split('#').next().unwrap_or_default().trim())
+			.filter(|line| !line.is_empty())
+			.map(|line| Word {
+				text: line.to_owned(),
+				category: name.clone(),
+				difficulty: Difficulty::default(),
+			})
+			.collect()
+	};
+
+	Ok(words)
+}
+
+/// The `assets/words` folder, if one can be found near the working directory.
+pub fn default_folder() -> Option<PathBuf> {
+	find_folder::Search::ParentsThenKids(3, 3).for_folder("assets").ok()
+		.map(|assets| assets.join("words"))
+		.filter(|words| words.is_dir())
+}
+
+/// The words a session picks from, remembering which have been offered so that none comes
+/// up again until the rest of the list has been used.
+pub struct WordList {
+	words: Vec<Word>,
+	used: HashSet<String>,
+}
+
+impl WordList {
+	pub fn new(words: Vec<Word>) -> Self {
+		let mut seen = HashSet::new();
+		let words = words.into_iter()
+			.filter(|word| seen.insert(word.text.to_lowercase()))
+			.collect();
+
+		WordList {
+			words,
+			used: HashSet::new(),
+		}
+	}
+
+	/// Loads the packs in `folder` whose file name or category is in `packs` (every pack when
+	/// `packs` is empty), keeping only the words of the given difficulty if there is one.
+	pub fn load(folder: &Path, packs: &[String], difficulty: Option<Difficulty>) -> Result<Self, Box<dyn Error>> {
+		let mut paths: Vec<PathBuf> = fs::read_dir(folder)
+			.map_err(|e| format!("Could not read word packs in '{}': {}", folder.display(), e))?
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|path| path.extension().is_some_and(|ext| ext == "txt" || ext == "toml"))
+			.collect();
+		paths.sort();
+
+		let mut words = Vec::new();
+		let mut found = HashSet::new();
+		for path in paths {
+			let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_owned();
+			let whole_pack = packs.is_empty() || packs.contains(&name);
+			if whole_pack {
+				found.insert(name);
+			}
+
+			for word in load_pack(&path)? {
+				if whole_pack || packs.contains(&word.category) {
+					found.insert(word.category.clone());
+					words.push(word);
+				}
+			}
+		}
+
+		if let Some(missing) = packs.iter().find(|pack| !found.contains(*pack)) {
+			return Err(format!("No word pack or category named '{}' in '{}'", missing, folder.display()).into());
+		}
+
+		if let Some(difficulty) = difficulty {
+			words.retain(|word| word.difficulty == difficulty);
+		}
+
+		if words.is_empty() {
+			return Err(format!("The selected word packs in '{}' contain no words", folder.display()).into());
+		}
+
+		Ok(WordList::new(words))
+	}
+
+	/// Loads the named packs (every pack when `packs` is empty) from `assets/words`, falling
+	/// back to the built-in words when that folder cannot be found and nothing was asked for.
+	pub fn select(packs: &[String], difficulty: Option<Difficulty>) -> Result<Self, Box<dyn Error>> {
+		match default_folder() {
+			Some(folder) => WordList::load(&folder, packs, difficulty),
+			None if packs.is_empty() && difficulty.is_none() => Ok(WordList::default()),
+			None => Err("Could not find the assets/words folder".into()),
+		}
+	}
+
+	pub fn words(&self) -> &[Word] {
+		&self.words
+	}
+
+	/// Picks up to `count` words that have not been offered yet this session, starting over
+	/// once too few are left.
+	pub fn choose(&mut self, count: usize) -> Vec<String> {
+		let unused = |list: &Self| -> Vec<String> {
+			list.words.iter()
+				.map(|word| word.text.clone())
+				.filter(|text| !list.used.contains(text))
+				.collect()
+		};
+
+		let mut candidates = unused(self);
+		if candidates.len() < count {
+			self.used.clear();
+			candidates = unused(self);
+		}
+
+		let chosen: Vec<String> = candidates
+			.choose_multiple(&mut rand::thread_rng(), count)
+			.cloned()
+			.collect();
+
+		self.used.extend(chosen.iter().cloned());
+		chosen
+	}
+}
+
+impl Default for WordList {
+	fn default() -> Self {
+		WordList::new(DEFAULT_WORDS.iter().map(|word| Word {
+			text: (*word).to_owned(),
+			category: "default".to_owned(),
+			difficulty: Difficulty::default(),
+		}).collect())
+	}
+}
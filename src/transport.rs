@@ -0,0 +1,149 @@
+//! What messages travel over: a TCP socket between machines, or an in-process channel when
+//! every player shares one window.
+
+use std::collections::VecDeque;
+use std::io::{self, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::message::GameMessage;
+
+/// The sending side of a connection. Each connection's reader is kept separately, as a plain
+/// `Read`, on the thread that waits for messages.
+pub trait Transport: Send {
+	fn send(&self, message: &GameMessage) -> io::Result<()>;
+
+	/// Writes out anything `send` held back.
+	fn flush(&self) -> io::Result<()> {
+		Ok(())
+	}
+
+	/// Ends the connection in both directions, so the readers at both ends see it close.
+	fn close(&self);
+}
+
+impl Transport for TcpStream {
+	fn send(&self, message: &GameMessage) -> io::Result<()> {
+		message.send(self)
+	}
+
+	fn close(&self) {
+		let _ = self.shutdown(Shutdown::Both);
+	}
+}
+
+/// A socket whose messages are held in a buffer until `flush`, so that everything sent in
+/// one frame goes out in one write. Nagle's algorithm is turned off, as it would only hold
+/// each write back further.
+pub struct BufferedTcpStream {
+	stream: TcpStream,
+	writer: Mutex<BufWriter<TcpStream>>,
+}
+
+impl BufferedTcpStream {
+	pub fn new(stream: TcpStream) -> io::Result<Self> {
+		stream.set_nodelay(true)?;
+		let writer = Mutex::new(BufWriter::new(stream.try_clone()?));
+		Ok(BufferedTcpStream { stream, writer })
+	}
+}
+
+impl Transport for BufferedTcpStream {
+	fn send(&self, message: &GameMessage) -> io::Result<()> {
+		message.send(&mut *self.writer.lock().unwrap())
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		self.writer.lock().unwrap().flush()
+	}
+
+	fn close(&self) {
+		let _ = self.flush();
+		let _ = self.stream.shutdown(Shutdown::Both);
+	}
+}
+
+/// One end of an in-process connection made by `local_pair`.
+pub struct LocalStream {
+	reader: LocalReader,
+	writer: LocalWriter,
+}
+
+/// Two connected ends; whatever one sends, the other reads.
+pub fn local_pair() -> (LocalStream, LocalStream) {
+	let (to_a, a_inbox) = channel();
+	let (to_b, b_inbox) = channel();
+
+	let a = LocalStream {
+		reader: LocalReader::new(a_inbox),
+		writer: LocalWriter { peer: to_b.clone(), own: to_a.clone() },
+	};
+
+	let b = LocalStream {
+		reader: LocalReader::new(b_inbox),
+		writer: LocalWriter { peer: to_a, own: to_b },
+	};
+
+	(a, b)
+}
+
+impl LocalStream {
+	pub fn split(self) -> (LocalReader, LocalWriter) {
+		(self.reader, self.writer)
+	}
+}
+
+/// Reads what the other end sent, in the order it was sent. An empty chunk marks the end of
+/// the connection.
+pub struct LocalReader {
+	inbox: Receiver<Vec<u8>>,
+	pending: VecDeque<u8>,
+	closed: bool,
+}
+
+impl LocalReader {
+	fn new(inbox: Receiver<Vec<u8>>) -> Self {
+		LocalReader {
+			inbox,
+			pending: VecDeque::new(),
+			closed: false,
+		}
+	}
+}
+
+impl Read for LocalReader {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		while self.pending.is_empty() && !self.closed && !buf.is_empty() {
+			match self.inbox.recv() {
+				Ok(bytes) if !bytes.is_empty() => self.pending.extend(bytes),
+				_ => self.closed = true,
+			}
+		}
+
+		let len = buf.len().min(self.pending.len());
+		for (byte, pending) in buf.iter_mut().zip(self.pending.drain(..len)) {
+			*byte = pending;
+		}
+
+		Ok(len)
+	}
+}
+
+/// Sends whole messages to the other end. It can also reach its own end's reader, so that
+/// closing the connection ends both, like shutting down a socket.
+pub struct LocalWriter {
+	peer: Sender<Vec<u8>>,
+	own: Sender<Vec<u8>>,
+}
+
+impl Transport for LocalWriter {
+	fn send(&self, message: &GameMessage) -> io::Result<()> {
+		self.peer.send(message.encode()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+	}
+
+	fn close(&self) {
+		let _ = self.peer.send(Vec::new());
+		let _ = self.own.send(Vec::new());
+	}
+}
@@ -0,0 +1,207 @@
+use image::Rgba;
+use pictionary::canvas::{Brush, BrushShape, CanvasOperation, Color, Picture};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const RED: Color = Color::new(255, 0, 0);
+const BLUE: Color = Color::new(0, 0, 255);
+
+fn rgba(color: Color) -> Rgba<u8> {
+	Rgba([color.r, color.g, color.b, 255])
+}
+
+fn pen() -> Brush {
+	Brush::new(1, BrushShape::Square)
+}
+
+#[test]
+fn undo_removes_the_whole_last_stroke_and_redo_restores_it() {
+	let mut picture = Picture::new(10, 10);
+	picture.apply(CanvasOperation::Clear);
+
+	picture.apply(CanvasOperation::Pixel(1, 1, RED, pen()));
+	picture.apply(CanvasOperation::Pixel(5, 5, BLUE, pen()));
+	picture.apply(CanvasOperation::Line(5, 5, 8, 5, BLUE, pen()));
+
+	picture.apply(CanvasOperation::Undo);
+	assert_eq!(*picture.image().get_pixel(1, 1), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(5, 5), WHITE);
+	assert_eq!(*picture.image().get_pixel(7, 5), WHITE);
+
+	picture.apply(CanvasOperation::Redo);
+	assert_eq!(*picture.image().get_pixel(5, 5), rgba(BLUE));
+	assert_eq!(*picture.image().get_pixel(7, 5), rgba(BLUE));
+}
+
+#[test]
+fn a_new_stroke_discards_the_redo_stack() {
+	let mut picture = Picture::new(10, 10);
+	picture.apply(CanvasOperation::Clear);
+
+	picture.apply(CanvasOperation::Pixel(1, 1, RED, pen()));
+	picture.apply(CanvasOperation::Undo);
+	picture.apply(CanvasOperation::Pixel(2, 2, BLUE, pen()));
+	picture.apply(CanvasOperation::Redo);
+
+	assert_eq!(*picture.image().get_pixel(1, 1), WHITE);
+	assert_eq!(*picture.image().get_pixel(2, 2), rgba(BLUE));
+}
+
+#[test]
+fn a_line_that_does_not_carry_on_from_the_last_stroke_starts_its_own() {
+	let mut picture = Picture::new(10, 10);
+	picture.apply(CanvasOperation::Clear);
+
+	picture.apply(CanvasOperation::Pixel(1, 1, RED, pen()));
+	picture.apply(CanvasOperation::Pixel(3, 3, RED, pen()));
+	picture.apply(CanvasOperation::Undo);
+	picture.apply(CanvasOperation::Line(5, 5, 8, 5, BLUE, pen()));
+
+	// The line discarded the undone pixel rather than joining the stroke before it.
+	picture.apply(CanvasOperation::Redo);
+	assert_eq!(*picture.image().get_pixel(3, 3), WHITE);
+
+	picture.apply(CanvasOperation::Undo);
+	assert_eq!(*picture.image().get_pixel(1, 1), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(7, 5), WHITE);
+
+	picture.apply(CanvasOperation::Redo);
+	assert_eq!(*picture.image().get_pixel(1, 1), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(7, 5), rgba(BLUE));
+}
+
+#[test]
+fn fill_stops_at_differently_colored_pixels() {
+	let mut picture = Picture::new(10, 10);
+	picture.apply(CanvasOperation::Clear);
+	picture.apply(CanvasOperation::Line(0, 4, 10, 4, RED, pen()));
+
+	picture.apply(CanvasOperation::Fill(3, 1, BLUE));
+	assert_eq!(*picture.image().get_pixel(9, 0), rgba(BLUE));
+	assert_eq!(*picture.image().get_pixel(0, 3), rgba(BLUE));
+	assert_eq!(*picture.image().get_pixel(5, 4), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(5, 5), WHITE);
+}
+
+#[test]
+fn undo_after_a_restore_repaints_over_the_snapshot() {
+	let mut snapshot = Picture::new(10, 10);
+	snapshot.apply(CanvasOperation::Clear);
+	snapshot.apply(CanvasOperation::Pixel(1, 1, RED, pen()));
+
+	let mut picture = Picture::new(10, 10);
+	picture.apply(CanvasOperation::Clear);
+	picture.apply(CanvasOperation::Restore(snapshot.image().clone()));
+	picture.apply(CanvasOperation::Pixel(5, 5, BLUE, pen()));
+	assert_eq!(*picture.image().get_pixel(5, 5), rgba(BLUE));
+
+	picture.apply(CanvasOperation::Undo);
+	assert_eq!(*picture.image().get_pixel(1, 1), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(5, 5), WHITE);
+
+	picture.apply(CanvasOperation::Restore(Picture::new(20, 20).image().clone()));
+	assert_eq!(*picture.image().get_pixel(1, 1), rgba(RED));
+}
+
+#[test]
+fn antialiased_round_brush_blends_its_edges() {
+	let hard = Brush::new(5, BrushShape::Round);
+	let soft = hard.with_antialiasing(true);
+
+	let mut picture = Picture::new(20, 20);
+	picture.apply(CanvasOperation::Clear);
+	picture.apply(CanvasOperation::Line(5, 10, 15, 10, RED, hard));
+	assert!(picture.image().pixels().all(|p| *p == WHITE || *p == rgba(RED)));
+	// Round caps reach past both ends.
+	assert_eq!(*picture.image().get_pixel(4, 10), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(16, 10), rgba(RED));
+
+	let mut picture = Picture::new(20, 20);
+	picture.apply(CanvasOperation::Clear);
+	picture.apply(CanvasOperation::Line(5, 10, 15, 10, RED, soft));
+	assert_eq!(*picture.image().get_pixel(10, 10), rgba(RED));
+	assert_eq!(*picture.image().get_pixel(10, 5), WHITE);
+	let edge = *picture.image().get_pixel(3, 11);
+	assert!(edge != WHITE && edge != rgba(RED), "{:?}", edge);
+}
+
+#[test]
+fn smoothed_strokes_repaint_exactly_after_undo_and_redo() {
+	let brush = Brush::new(3, BrushShape::Round).with_antialiasing(true).with_smoothing(true);
+	let stroke = [
+		CanvasOperation::Pixel(2, 15, BLUE, brush),
+		CanvasOperation::Line(2, 15, 6, 4, BLUE, brush),
+		CanvasOperation::Line(6, 4, 12, 14, BLUE, brush),
+		CanvasOperation::Line(12, 14, 17, 3, BLUE, brush),
+	];
+
+	let mut picture = Picture::new(20, 20);
+	picture.apply(CanvasOperation::Clear);
+	for operation in stroke.iter() {
+		picture.apply(operation.clone());
+	}
+	let drawn = picture.image().clone();
+
+	let mut straight = Picture::new(20, 20);
+	straight.apply(CanvasOperation::Clear);
+	for operation in stroke.iter() {
+		straight.apply(match *operation {
+			CanvasOperation::Line(x1, y1, x2, y2, color, brush) => CanvasOperation::Line(x1, y1, x2, y2, color, brush.with_smoothing(false)),
+			ref operation => operation.clone(),
+		});
+	}
+	assert!(*straight.image() != drawn);
+
+	picture.apply(CanvasOperation::Pixel(0, 0, RED, pen()));
+	picture.apply(CanvasOperation::Undo);
+	assert!(*picture.image() == drawn);
+
+	picture.apply(CanvasOperation::Undo);
+	picture.apply(CanvasOperation::Redo);
+	assert!(*picture.image() == drawn);
+}
+
+#[test]
+fn lines_of_a_stroke_do_not_darken_where_they_meet() {
+	let soft = Brush::new(4, BrushShape::Round).with_antialiasing(true);
+
+	let mut whole = Picture::new(20, 20);
+	whole.apply(CanvasOperation::Clear);
+	whole.apply(CanvasOperation::Pixel(3, 3, RED, soft));
+	whole.apply(CanvasOperation::Line(3, 3, 15, 15, RED, soft));
+
+	let mut pieces = Picture::new(20, 20);
+	pieces.apply(CanvasOperation::Clear);
+	pieces.apply(CanvasOperation::Pixel(3, 3, RED, soft));
+	pieces.apply(CanvasOperation::Line(3, 3, 9, 9, RED, soft));
+	pieces.apply(CanvasOperation::Line(9, 9, 15, 15, RED, soft));
+	assert!(*pieces.image() == *whole.image());
+}
+
+#[test]
+fn long_smoothed_lines_are_quick_to_draw() {
+	let brush = Brush::new(Brush::MAX_SIZE, BrushShape::Round).with_antialiasing(true).with_smoothing(true);
+	let mut picture = Picture::new(400, 400);
+	picture.apply(CanvasOperation::Clear);
+
+	let start = std::time::Instant::now();
+	picture.apply(CanvasOperation::Line(0, 0, 399, 399, RED, brush));
+	picture.apply(CanvasOperation::Line(399, 399, 0, 399, RED, brush));
+	assert!(start.elapsed() < std::time::Duration::from_millis(500), "{:?}", start.elapsed());
+}
+
+#[test]
+fn oversized_brushes_are_painted_at_the_largest_size() {
+	let huge = Brush::new(u8::MAX, BrushShape::Round).with_antialiasing(true).with_smoothing(true);
+	let largest = Brush::new(Brush::MAX_SIZE, BrushShape::Round).with_antialiasing(true).with_smoothing(true);
+
+	let mut pictures = [Picture::new(400, 400), Picture::new(400, 400)];
+	for (picture, brush) in pictures.iter_mut().zip([huge, largest]) {
+		picture.apply(CanvasOperation::Clear);
+		picture.apply(CanvasOperation::Pixel(0, 0, RED, brush));
+		for i in 0..100 {
+			picture.apply(CanvasOperation::Line(i * 4, i * 4, i * 4 + 4, i * 4 + 4, RED, brush));
+		}
+	}
+
+	assert!(*pictures[0].image() == *pictures[1].image());
+}
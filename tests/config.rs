@@ -0,0 +1,73 @@
+use pictionary::config::{Config, Role, Settings};
+use pictionary::server::words::Difficulty;
+
+fn args(line: &str) -> Vec<String> {
+	line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn command_line_options_override_the_config_file() {
+	let file = r#"
+		connect = "10.0.0.2:1818"
+		name = "alice"
+		round-seconds = 60
+		words = ["animals"]
+		hints = [25]
+	"#;
+
+	let config = Config::parse(&args("--round-seconds 80 --choices 4 --difficulty hard --hints none"), Some(file)).unwrap();
+	assert_eq!(config.role, Role::Connect("10.0.0.2:1818".to_owned()));
+	assert_eq!(config.name, "alice");
+	assert_eq!(config.settings, Settings {
+		round_seconds: 80,
+		choices: 4,
+		rounds: 0,
+		hints: Vec::new(),
+		words: vec!["animals".to_owned()],
+		difficulty: Some(Difficulty::Hard),
+		canvas_size: 100,
+	});
+}
+
+#[test]
+fn a_role_on_the_command_line_replaces_the_files() {
+	let file = r#"
+		connect = "10.0.0.2:1818"
+		name = "alice"
+	"#;
+
+	let config = Config::parse(&args("--host 0.0.0.0:1818"), Some(file)).unwrap();
+	assert_eq!(config.role, Role::Host("0.0.0.0:1818".to_owned()));
+	assert_eq!(config.name, "alice");
+
+	let config = Config::parse(&args("--local alice,bob"), Some(file)).unwrap();
+	assert_eq!(config.role, Role::Local(vec!["alice".to_owned(), "bob".to_owned()]));
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --spectate 10.0.0.2:1818"), Some(file)).is_err());
+}
+
+#[test]
+fn invalid_command_lines_are_rejected() {
+	assert!(Config::parse(&args("--name bob"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --connect 127.0.0.1:1818"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --choices 12"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --rounds"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --colour red"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818"), Some("colour = \"red\"")).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --replay game.pictlog"), None).is_err());
+	assert!(Config::parse(&args("--replay game.pictlog --speed 0"), None).is_err());
+	assert!(Config::parse(&args("--local alice"), None).is_err());
+	assert!(Config::parse(&args("--host 0.0.0.0:1818 --canvas-size 300"), None).is_err());
+	assert_eq!(Config::parse(&args("--host 0.0.0.0:1818 --canvas-size 400"), None).unwrap().settings.canvas_size, 400);
+	assert!(Config::parse(&args("--local alice,alice"), None).is_err());
+	assert!(Config::parse(&args("--spectate 127.0.0.1:1818 --connect 127.0.0.1:1818"), None).is_err());
+	assert_eq!(Config::parse(&args("--spectate 127.0.0.1:1818"), None).unwrap().role, Role::Spectate("127.0.0.1:1818".to_owned()));
+	assert_eq!(Config::parse(&args("--local alice,bob"), None).unwrap().role, Role::Local(vec!["alice".to_owned(), "bob".to_owned()]));
+
+	let config = Config::parse(&args("--replay game.pictlog --speed 4"), Some("record = \"again.pictlog\"")).unwrap();
+	assert_eq!(config.role, Role::Replay("game.pictlog".to_owned(), 4.0));
+	assert_eq!(config.record.as_deref(), Some("again.pictlog"));
+
+	let config = Config::parse(&args("--host 0.0.0.0:1818 --words animals,food"), None).unwrap();
+	assert_eq!(config.role, Role::Host("0.0.0.0:1818".to_owned()));
+	assert_eq!(config.settings.words, ["animals", "food"]);
+}
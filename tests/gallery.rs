@@ -0,0 +1,29 @@
+use std::fs;
+use image::Rgba;
+use pictionary::canvas::Image;
+use pictionary::gallery::{contact_sheet, drawings_since};
+
+#[test]
+fn contact_sheet_tiles_recent_drawings_in_save_order() {
+	let folder = std::env::temp_dir().join(format!("pictionary-gallery-{}", std::process::id()));
+	fs::create_dir_all(&folder).unwrap();
+
+	let red = Image::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+	let blue = Image::from_pixel(4, 4, Rgba([0, 0, 255, 255]));
+	red.save(folder.join("200_tree.png")).unwrap();
+	blue.save(folder.join("100_book.png")).unwrap();
+	blue.save(folder.join("50_bike.png")).unwrap();
+	blue.save(folder.join("gallery_300.png")).unwrap();
+
+	let drawings = drawings_since(&folder, 100).unwrap();
+	let names: Vec<_> = drawings.iter().map(|p| p.file_name().unwrap().to_owned()).collect();
+	assert_eq!(names, ["100_book.png", "200_tree.png"]);
+
+	let sheet = contact_sheet(&drawings).unwrap();
+	assert_eq!(sheet.dimensions(), (430, 220));
+	assert_eq!(*sheet.get_pixel(110, 110), Rgba([0, 0, 255, 255]));
+	assert_eq!(*sheet.get_pixel(320, 110), Rgba([255, 0, 0, 255]));
+	assert_eq!(*sheet.get_pixel(5, 5), Rgba([255, 255, 255, 255]));
+
+	fs::remove_dir_all(&folder).unwrap();
+}
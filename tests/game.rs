@@ -0,0 +1,64 @@
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use piston_window::{Button, ButtonArgs, ButtonState, Event, Input, Key, Loop, Motion, MouseButton, RenderArgs};
+use pictionary::canvas::{Brush, CanvasOperation};
+use pictionary::game::Game;
+use pictionary::message::{CAPABILITIES, GameMessage, PROTOCOL_VERSION, parse_game_message};
+use pictionary::transport::{Transport, local_pair};
+
+fn button(button: Button, state: ButtonState) -> Event {
+	Event::Input(Input::Button(ButtonArgs { state, button, scancode: None }), None)
+}
+
+/// Moves the cursor over the pixel at (x, y) of a 100 pixel canvas in an 800 pixel window.
+fn cursor(x: f64, y: f64) -> Event {
+	Event::Input(Input::Move(Motion::MouseCursor([x * 8.0 + 4.0, y * 8.0 + 4.0])), None)
+}
+
+#[test]
+fn erase_drags_are_sent_as_one_polyline() {
+	let (client, server) = local_pair();
+	let (sender, receiver) = channel();
+	let game = Game::local("alice".to_owned(), client, sender);
+
+	let (mut reader, writer) = server.split();
+	assert!(matches!(parse_game_message(&mut reader).unwrap(), GameMessage::Hello { .. }));
+	writer.send(&GameMessage::Hello {
+		protocol_version: PROTOCOL_VERSION,
+		client_name: "server".to_owned(),
+		capabilities: CAPABILITIES,
+		canvas_size: 100,
+		spectating: false,
+	}).unwrap();
+	writer.send(&GameMessage::Roster(1, vec![(1, "alice".to_owned()), (2, "bob".to_owned())])).unwrap();
+	writer.send(&GameMessage::SwapRoles(1)).unwrap();
+	writer.send(&GameMessage::WordChoices(vec!["tree".to_owned()])).unwrap();
+	// Shows up on the canvas once the word choices before it are in.
+	writer.send(&GameMessage::Undo).unwrap();
+	while !matches!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), CanvasOperation::Undo) {}
+
+	let events = [
+		Event::Loop(Loop::Render(RenderArgs { ext_dt: 0.0, window_size: [800.0; 2], draw_size: [800; 2] })),
+		button(Button::Keyboard(Key::D1), ButtonState::Press),
+		cursor(50.0, 50.0),
+		button(Button::Mouse(MouseButton::Right), ButtonState::Press),
+		cursor(52.0, 50.0),
+		cursor(54.0, 51.0),
+		cursor(60.0, 55.0),
+		button(Button::Mouse(MouseButton::Right), ButtonState::Release),
+	];
+	for event in events {
+		game.lock().unwrap().process_event(event);
+	}
+
+	assert_eq!(parse_game_message(&mut reader).unwrap(), GameMessage::PickWord(0));
+	assert!(matches!(parse_game_message(&mut reader).unwrap(), GameMessage::Erase(50, 50, _)));
+	match parse_game_message(&mut reader).unwrap() {
+		GameMessage::ErasePolyline { brush, points } => {
+			assert_eq!(brush.size, Brush::default().size);
+			assert_eq!(points, [(50, 50), (52, 50), (54, 51), (60, 55)]);
+		},
+		message => panic!("expected an erase polyline, got {:?}", message),
+	}
+}
@@ -0,0 +1,24 @@
+use pictionary::layout::{Layout, VIEW_SIZE};
+
+#[test]
+fn view_fills_a_square_window() {
+	let layout = Layout::new([VIEW_SIZE; 2]);
+	assert_eq!(layout.scale(), 1.0);
+	assert_eq!(layout.to_canvas([0.0, 0.0], 100, 100), Some((0, 0)));
+	assert_eq!(layout.to_canvas([404.0, 799.0], 100, 100), Some((50, 99)));
+	assert_eq!(layout.to_canvas([404.0, 799.0], 200, 200), Some((101, 199)));
+}
+
+#[test]
+fn wide_window_is_letterboxed_left_and_right() {
+	// 1000x400: the view is scaled to 400x400 and starts 300 pixels in.
+	let layout = Layout::new([1000.0, 400.0]);
+	assert_eq!(layout.scale(), 0.5);
+	assert_eq!(layout.to_view([300.0, 0.0]), Some([0.0, 0.0]));
+	assert_eq!(layout.to_view([500.0, 200.0]), Some([400.0, 400.0]));
+	assert_eq!(layout.to_canvas([699.0, 399.0], 100, 100), Some((99, 99)));
+
+	assert_eq!(layout.to_view([299.0, 200.0]), None);
+	assert_eq!(layout.to_view([700.0, 200.0]), None);
+	assert_eq!(Layout::new([0.0, 0.0]).to_view([0.0, 0.0]), None);
+}
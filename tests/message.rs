@@ -0,0 +1,154 @@
+use std::io::Cursor;
+
+use pictionary::canvas::{Brush, BrushShape, Color};
+use pictionary::message::{CAPABILITIES, GameMessage, PROTOCOL_VERSION, ProtocolError, RoundResult, Verdict, parse_game_message};
+
+fn every_message() -> Vec<GameMessage> {
+	let red = Color::new(255, 0, 0);
+	let round = Brush::new(5, BrushShape::Round);
+	let square = Brush::new(Brush::MAX_SIZE, BrushShape::Square);
+	let soft = round.with_antialiasing(true);
+	let smooth = round.with_antialiasing(true).with_smoothing(true);
+
+	vec![
+		GameMessage::Draw(1, 99, red, round),
+		GameMessage::SetTimeRemaining(87),
+		GameMessage::SetWordSkeleton("_ _ a _".to_owned()),
+		GameMessage::Guess(2, "snow man".to_owned()),
+		GameMessage::GuessResult(2, Verdict::Wrong),
+		GameMessage::GuessResult(2, Verdict::Close),
+		GameMessage::GuessResult(3, Verdict::Correct("snowman".to_owned())),
+		GameMessage::GameOver("turtle".to_owned()),
+		GameMessage::SwapRoles(4),
+		GameMessage::Erase(50, 60, square),
+		GameMessage::DrawLine(0, 1, 98, 99, Color::new(1, 2, 3), square),
+		GameMessage::EraseLine(9, 8, 7, 6, round),
+		GameMessage::Draw(3, 4, red, soft),
+		GameMessage::DrawLine(5, 6, 7, 8, red, smooth),
+		GameMessage::EraseLine(1, 2, 3, 4, square.with_smoothing(true)),
+		GameMessage::Hello { protocol_version: PROTOCOL_VERSION, client_name: "alice".to_owned(), capabilities: CAPABILITIES, canvas_size: 200, spectating: false },
+		GameMessage::Hello { protocol_version: PROTOCOL_VERSION, client_name: "big screen".to_owned(), capabilities: 0, canvas_size: 0, spectating: true },
+		GameMessage::Leave(5),
+		GameMessage::Roster(1, vec![(1, "alice".to_owned()), (2, "bøb".to_owned())]),
+		GameMessage::Roster(1, Vec::new()),
+		GameMessage::WordChoices(vec!["bike".to_owned(), "ice cream".to_owned(), "tree".to_owned()]),
+		GameMessage::PickWord(2),
+		GameMessage::NextRound,
+		GameMessage::Reject("Server speaks protocol version 9".to_owned()),
+		GameMessage::Fill(10, 20, Color::new(0, 0, 255)),
+		GameMessage::Undo,
+		GameMessage::Redo,
+		GameMessage::ScoreUpdate(vec![(1, 150), (2, 0)]),
+		GameMessage::Settings { round_seconds: 60, choices: 4, rounds: 3 },
+		GameMessage::MatchOver(vec![
+			RoundResult { word: "bike".to_owned(), winner: Some("bob".to_owned()), seconds: 12 },
+			RoundResult { word: "tree".to_owned(), winner: None, seconds: 100 },
+		]),
+		GameMessage::Answer("ice cream".to_owned()),
+		GameMessage::CanvasSnapshot(3, 2, [[255, 255, 255, 255]; 5].iter().flatten().copied().chain([1, 2, 3, 255]).collect()),
+		GameMessage::Polyline { color: red, brush: smooth, points: vec![(10, 10), (11, 9), (11, 9), (0, 399), (u16::MAX, 0)] },
+		GameMessage::Polyline { color: red, brush: square, points: vec![(5, 6)] },
+		GameMessage::Polyline { color: red, brush: round, points: Vec::new() },
+		GameMessage::ErasePolyline { brush: soft, points: vec![(7, 7), (6, 8), (300, 2)] },
+	]
+}
+
+#[test]
+fn every_message_survives_a_round_trip() {
+	for message in every_message() {
+		let mut bytes = Vec::new();
+		message.send(&mut bytes).unwrap();
+		assert_eq!(bytes, message.encode());
+
+		let mut reader = Cursor::new(bytes);
+		assert_eq!(parse_game_message(&mut reader).unwrap(), message);
+		assert_eq!(reader.position() as usize, reader.get_ref().len(), "{:?} left bytes unread", message);
+	}
+}
+
+#[test]
+fn messages_are_read_back_to_back_from_one_stream() {
+	let mut bytes = Vec::new();
+	for message in every_message() {
+		message.send(&mut bytes).unwrap();
+	}
+
+	let mut reader = bytes.as_slice();
+	for message in every_message() {
+		assert_eq!(parse_game_message(&mut reader).unwrap(), message);
+	}
+	assert!(matches!(parse_game_message(&mut reader), Err(ProtocolError::Eof)));
+}
+
+#[test]
+fn coordinates_are_checked_against_the_canvas() {
+	let red = Color::new(255, 0, 0);
+	assert!(GameMessage::Draw(99, 99, red, Brush::default()).in_bounds(100));
+	assert!(!GameMessage::Draw(100, 0, red, Brush::default()).in_bounds(100));
+	assert!(GameMessage::DrawLine(0, 0, 199, 150, red, Brush::default()).in_bounds(200));
+	assert!(!GameMessage::EraseLine(0, 0, 5, 200, Brush::default()).in_bounds(200));
+	assert!(!GameMessage::Fill(0, 400, red).in_bounds(200));
+	assert!(!GameMessage::CanvasSnapshot(100, 100, vec![255; 100 * 100 * 4]).in_bounds(200));
+	assert!(GameMessage::Guess(1, "tree".to_owned()).in_bounds(100));
+
+	let polyline = |points| GameMessage::Polyline { color: red, brush: Brush::default(), points };
+	assert!(polyline(vec![(0, 0), (199, 199)]).in_bounds(200));
+	assert!(!polyline(vec![(0, 0), (200, 5), (3, 3)]).in_bounds(200));
+}
+
+#[test]
+fn polylines_are_delta_encoded_and_split_into_lines() {
+	let red = Color::new(255, 0, 0);
+	let points = vec![(50, 50), (51, 50), (51, 48), (60, 30)];
+	let polyline = GameMessage::Polyline { color: red, brush: Brush::default(), points };
+
+	// Id, color, brush, count, the first point in full and a byte per small step after it.
+	assert_eq!(polyline.encode().len(), 1 + 3 + 3 + 4 + 4 + 3 * 2);
+	assert_eq!(polyline.lines(), vec![
+		GameMessage::DrawLine(50, 50, 51, 50, red, Brush::default()),
+		GameMessage::DrawLine(51, 50, 51, 48, red, Brush::default()),
+		GameMessage::DrawLine(51, 48, 60, 30, red, Brush::default()),
+	]);
+	let erase = GameMessage::ErasePolyline { brush: Brush::default(), points: vec![(1, 1), (2, 3)] };
+	assert_eq!(erase.lines(), vec![GameMessage::EraseLine(1, 1, 2, 3, Brush::default())]);
+	assert!(!erase.in_bounds(2));
+	assert!(GameMessage::Undo.lines().is_empty());
+}
+
+#[test]
+fn corrupt_streams_are_rejected() {
+	assert!(matches!(parse_game_message(&mut [200u8].as_slice()), Err(ProtocolError::UnknownMessage(200))));
+
+	let truncated = GameMessage::Guess(2, "snowman".to_owned()).encode();
+	assert!(matches!(parse_game_message(&mut &truncated[..truncated.len() - 1]), Err(ProtocolError::Eof)));
+
+	let long = GameMessage::Reject("x".repeat(2000)).encode();
+	assert!(matches!(parse_game_message(&mut long.as_slice()), Err(ProtocolError::StringTooLong(2000))));
+
+	let mut invalid = GameMessage::GameOver("ab".to_owned()).encode();
+	invalid[5] = 0xff;
+	assert!(matches!(parse_game_message(&mut invalid.as_slice()), Err(ProtocolError::InvalidUtf8)));
+
+	// The runs describe one pixel more than the 2x2 header promises.
+	let mut snapshot = GameMessage::CanvasSnapshot(2, 2, vec![0; 16]).encode();
+	snapshot[13] = 5;
+	assert!(matches!(parse_game_message(&mut snapshot.as_slice()), Err(ProtocolError::InvalidSnapshot)));
+
+	let mut draw = GameMessage::Draw(1, 1, Color::new(0, 0, 0), Brush::default()).encode();
+	draw[12] = 0;
+	assert!(matches!(parse_game_message(&mut draw.as_slice()), Err(ProtocolError::InvalidBrush(0))));
+
+	let points = (0..400).map(|i| (i, i)).collect();
+	let mut polyline = GameMessage::Polyline { color: Color::new(0, 0, 0), brush: Brush::default(), points }.encode();
+	polyline[4] = 255;
+	assert!(matches!(parse_game_message(&mut polyline.as_slice()), Err(ProtocolError::InvalidBrush(255))));
+
+	// A step of -2 from x = 1 would land below zero.
+	let mut polyline = GameMessage::Polyline { color: Color::new(0, 0, 0), brush: Brush::default(), points: vec![(1, 1), (2, 1)] }.encode();
+	polyline[15] = 3;
+	assert!(matches!(parse_game_message(&mut polyline.as_slice()), Err(ProtocolError::InvalidPolyline)));
+
+	let mut polyline = GameMessage::Polyline { color: Color::new(0, 0, 0), brush: Brush::default(), points: Vec::new() }.encode();
+	polyline[7..11].copy_from_slice(&100_000u32.to_be_bytes());
+	assert!(matches!(parse_game_message(&mut polyline.as_slice()), Err(ProtocolError::InvalidPolyline)));
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::net::TcpStream;
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+use piston_window::{Button, ButtonArgs, ButtonState, Event, Input, Key};
+use pictionary::canvas::{Brush, CanvasOperation, Color};
+use pictionary::config::Config;
+use pictionary::game::Game;
+use pictionary::message::{CAPABILITIES, GameMessage, PROTOCOL_VERSION, parse_game_message};
+use pictionary::pictlog::{self, Recorder};
+
+#[test]
+fn recording_is_served_back_in_order_and_sped_up() {
+	let path = std::env::temp_dir().join(format!("pictionary-{}.pictlog", std::process::id()));
+	let line = GameMessage::DrawLine(1, 2, 3, 4, Color::new(255, 0, 0), Brush::default());
+	let mut recorder = Recorder::create(&path, 200).unwrap();
+	recorder.received(&GameMessage::Roster(1, vec![(1, "alice".to_owned())])).unwrap();
+	recorder.received(&GameMessage::SwapRoles(1)).unwrap();
+	recorder.sent(&GameMessage::PickWord(0)).unwrap();
+	std::thread::sleep(Duration::from_millis(500));
+	recorder.sent(&line).unwrap();
+	drop(recorder);
+
+	let recording = pictlog::read(&path).unwrap();
+	assert_eq!(recording.canvas_size, 200);
+	assert_eq!(recording.entries.len(), 3);
+	assert!(recording.entries[2].time >= Duration::from_millis(500));
+
+	let address = pictlog::serve(&path, 10.0).unwrap();
+	let mut stream = TcpStream::connect(address).unwrap();
+	stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	GameMessage::Hello {
+		protocol_version: PROTOCOL_VERSION,
+		client_name: "viewer".to_owned(),
+		capabilities: CAPABILITIES,
+		canvas_size: 0,
+		spectating: false,
+	}.send(&stream).unwrap();
+
+	let start = Instant::now();
+	assert!(matches!(parse_game_message(&mut stream).unwrap(), GameMessage::Hello { canvas_size: 200, .. }));
+	assert!(matches!(parse_game_message(&mut stream).unwrap(), GameMessage::Roster(1, _)));
+	assert_eq!(parse_game_message(&mut stream).unwrap(), GameMessage::SwapRoles(1));
+	assert_eq!(parse_game_message(&mut stream).unwrap(), line);
+	assert!(start.elapsed() < Duration::from_millis(400));
+
+	fs::write(&path, b"not a recording").unwrap();
+	assert!(pictlog::read(&path).is_err());
+	fs::remove_file(&path).unwrap();
+}
+
+fn press(key: Key) -> Event {
+	Event::Input(Input::Button(ButtonArgs { state: ButtonState::Press, button: Button::Keyboard(key), scancode: None }), None)
+}
+
+#[test]
+fn a_drawers_recording_is_only_watched() {
+	let path = std::env::temp_dir().join(format!("pictionary-drawer-{}.pictlog", std::process::id()));
+	let line = GameMessage::DrawLine(1, 2, 3, 4, Color::new(255, 0, 0), Brush::default());
+	let mut recorder = Recorder::create(&path, 100).unwrap();
+	recorder.received(&GameMessage::Roster(1, vec![(1, "alice".to_owned()), (2, "bob".to_owned())])).unwrap();
+	recorder.received(&GameMessage::SwapRoles(1)).unwrap();
+	recorder.received(&GameMessage::WordChoices(vec!["tree".to_owned(), "bike".to_owned()])).unwrap();
+	recorder.received(&GameMessage::Answer("tree".to_owned())).unwrap();
+	recorder.sent(&line).unwrap();
+	drop(recorder);
+
+	let args = ["--replay".to_owned(), path.display().to_string(), "--speed".to_owned(), "10".to_owned()];
+	let (sender, receiver) = channel();
+	let game = Game::new(Config::parse(&args, None).unwrap(), sender);
+	let timeout = Duration::from_secs(5);
+	while !matches!(receiver.recv_timeout(timeout).unwrap(), CanvasOperation::Line(..)) {}
+
+	// A drawer would pick the first word and undo the line.
+	for key in [Key::D1, Key::LCtrl, Key::Z] {
+		game.lock().unwrap().process_event(press(key));
+	}
+	assert!(receiver.recv_timeout(Duration::from_millis(300)).is_err());
+	fs::remove_file(&path).unwrap();
+}
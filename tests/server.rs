@@ -0,0 +1,649 @@
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use pictionary::canvas::{Brush, BrushShape, Color};
+use pictionary::config::Settings;
+use pictionary::message::{CAPABILITIES, Capabilities, capability, GameMessage, PROTOCOL_VERSION, ProtocolError, RECONNECT_SECONDS, RoundResult, Verdict, parse_game_message};
+use pictionary::server::Server;
+use pictionary::server::words::{Difficulty, Word, WordList};
+use pictionary::transport::{LocalReader, LocalStream, Transport};
+
+fn start_server() -> SocketAddr {
+	start(Server::bind("127.0.0.1:0").unwrap())
+}
+
+fn start(server: Server) -> SocketAddr {
+	let address = server.local_addr().unwrap();
+	thread::spawn(move || server.run());
+	address
+}
+
+fn connect(address: SocketAddr, protocol_version: u32, capabilities: Capabilities, name: &str) -> TcpStream {
+	let stream = TcpStream::connect(address).unwrap();
+	stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	GameMessage::Hello {
+		protocol_version,
+		client_name: name.to_owned(),
+		capabilities,
+		canvas_size: 0,
+		spectating: false,
+	}.send(&stream).unwrap();
+	stream
+}
+
+fn spectate(address: SocketAddr, name: &str) -> TcpStream {
+	let mut stream = TcpStream::connect(address).unwrap();
+	stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	GameMessage::Hello {
+		protocol_version: PROTOCOL_VERSION,
+		client_name: name.to_owned(),
+		capabilities: CAPABILITIES,
+		canvas_size: 0,
+		spectating: true,
+	}.send(&stream).unwrap();
+
+	assert!(matches!(next(&mut stream), GameMessage::Hello { .. }));
+	assert!(matches!(next(&mut stream), GameMessage::Settings { .. }));
+	stream
+}
+
+fn join(address: SocketAddr, name: &str) -> TcpStream {
+	join_with(address, name, CAPABILITIES)
+}
+
+fn join_with(address: SocketAddr, name: &str, capabilities: Capabilities) -> TcpStream {
+	let mut stream = connect(address, PROTOCOL_VERSION, capabilities, name);
+	match next(&mut stream) {
+		GameMessage::Hello { protocol_version, .. } => assert_eq!(protocol_version, PROTOCOL_VERSION),
+		message => panic!("expected hello, got {:?}", message),
+	}
+
+	if capabilities & capability::SETTINGS != 0 {
+		assert!(matches!(next(&mut stream), GameMessage::Settings { .. }));
+	}
+	stream
+}
+
+/// Reads the next message, skipping the timer updates the server sends every second.
+fn next(stream: &mut TcpStream) -> GameMessage {
+	loop {
+		match parse_game_message(stream).unwrap() {
+			GameMessage::SetTimeRemaining(_) => continue,
+			message => return message,
+		}
+	}
+}
+
+fn roster(names: &[(u32, &str)]) -> Vec<(u32, String)> {
+	names.iter().map(|(id, name)| (*id, name.to_string())).collect()
+}
+
+/// Connects alice and bob and reads up to the start of the first round, returning alice's
+/// word choices.
+fn start_round(address: SocketAddr) -> (TcpStream, TcpStream, Vec<String>) {
+	let mut alice = join(address, "alice");
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, roster(&[(1, "alice")])));
+
+	let mut bob = join(address, "bob");
+	let both = roster(&[(1, "alice"), (2, "bob")]);
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, both.clone()));
+	assert_eq!(next(&mut bob), GameMessage::Roster(2, both));
+
+	assert_eq!(next(&mut alice), GameMessage::SwapRoles(1));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(1));
+
+	let words = match next(&mut alice) {
+		GameMessage::WordChoices(words) => words,
+		message => panic!("expected word choices, got {:?}", message),
+	};
+	assert_eq!(words.len(), 3);
+
+	(alice, bob, words)
+}
+
+#[test]
+fn first_player_draws_once_a_second_joins() {
+	start_round(start_server());
+}
+
+#[test]
+fn mismatched_protocol_version_is_rejected() {
+	let address = start_server();
+	let mut stream = connect(address, PROTOCOL_VERSION + 1, CAPABILITIES, "future");
+	assert!(matches!(next(&mut stream), GameMessage::Reject(_)));
+	assert!(parse_game_message(&mut stream).is_err());
+
+	let mut player = join(address, "present");
+	assert_eq!(next(&mut player), GameMessage::Roster(1, roster(&[(1, "present")])));
+}
+
+#[test]
+fn guests_are_sent_the_hosts_settings() {
+	let settings = Settings { round_seconds: 45, choices: 5, rounds: 3, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+
+	let mut alice = connect(address, PROTOCOL_VERSION, CAPABILITIES, "alice");
+	assert!(matches!(next(&mut alice), GameMessage::Hello { .. }));
+	assert_eq!(next(&mut alice), GameMessage::Settings { round_seconds: 45, choices: 5, rounds: 3 });
+
+	let mut bob = join(address, "bob");
+	assert!(matches!(next(&mut alice), GameMessage::Roster(1, _)));
+	assert!(matches!(next(&mut bob), GameMessage::Roster(2, _)));
+	assert!(matches!(next(&mut alice), GameMessage::Roster(1, _)));
+	assert!(matches!(next(&mut alice), GameMessage::SwapRoles(1)));
+	match next(&mut alice) {
+		GameMessage::WordChoices(words) => assert_eq!(words.len(), 5),
+		message => panic!("expected word choices, got {:?}", message),
+	}
+}
+
+#[test]
+fn unnamed_players_get_a_default_name() {
+	let address = start_server();
+	let mut player = join(address, "");
+	assert_eq!(next(&mut player), GameMessage::Roster(1, roster(&[(1, "Player 1")])));
+}
+
+#[test]
+fn correct_guess_ends_the_round() {
+	let (mut alice, mut bob, words) = start_round(start_server());
+	let word = words[0].clone();
+
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	let skeleton: String = word.chars().map(|c| if c == ' ' { ' ' } else { '_' }).collect();
+	assert_eq!(next(&mut bob), GameMessage::SetWordSkeleton(skeleton));
+
+	GameMessage::Guess(2, "not it at all".to_owned()).send(&bob).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Guess(2, "not it at all".to_owned()));
+	assert_eq!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Wrong));
+	assert_eq!(next(&mut alice), GameMessage::Guess(2, "not it at all".to_owned()));
+
+	let shouted = format!("  {}  ", word.to_uppercase().replace(' ', "   "));
+	GameMessage::Guess(2, shouted).send(&bob).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Correct(word.clone())));
+	assert_eq!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(word)));
+}
+
+#[test]
+fn very_long_rounds_score_without_overflowing() {
+	let settings = Settings { round_seconds: u32::MAX, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+	let (mut alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	GameMessage::Guess(2, words[0].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	let scores = match next(&mut alice) {
+		GameMessage::ScoreUpdate(scores) => scores,
+		message => panic!("expected a score update, got {:?}", message),
+	};
+	assert_eq!(scores[0], (1, 50));
+	assert!((99..=100).contains(&scores[1].1), "guesser scored {}", scores[1].1);
+}
+
+#[test]
+fn close_guesses_are_only_shared_with_the_drawer() {
+	let address = start_server();
+	let (mut alice, mut bob, words) = start_round(address);
+	let word = words[1].clone();
+	GameMessage::PickWord(1).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let mut carol = join(address, "carol");
+	assert!(matches!(next(&mut carol), GameMessage::Roster(3, _)));
+	assert!(matches!(next(&mut carol), GameMessage::SwapRoles(1)));
+	assert!(matches!(next(&mut carol), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut carol), GameMessage::CanvasSnapshot(..)));
+	assert!(matches!(next(&mut bob), GameMessage::Roster(2, _)));
+
+	let typo = format!("{}x", word);
+	GameMessage::Guess(2, typo.clone()).send(&bob).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Guess(2, typo.clone()));
+	assert_eq!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Close));
+
+	assert!(matches!(next(&mut alice), GameMessage::Roster(1, _)));
+	assert_eq!(next(&mut alice), GameMessage::Guess(2, typo));
+	assert_eq!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Close));
+
+	GameMessage::Guess(2, "nowhere near".to_owned()).send(&bob).unwrap();
+	assert_eq!(next(&mut carol), GameMessage::Guess(2, "nowhere near".to_owned()));
+}
+
+#[test]
+fn near_misses_at_short_words_are_not_close() {
+	let words = ["cat", "dog", "sun"].iter().map(|text| Word {
+		text: text.to_string(),
+		category: "animals".to_owned(),
+		difficulty: Difficulty::default(),
+	}).collect();
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_words(WordList::new(words)));
+	let (alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let plural = format!("{}s", words[0]);
+	GameMessage::Guess(2, plural.clone()).send(&bob).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Guess(2, plural));
+	assert_eq!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Wrong));
+}
+
+#[test]
+fn correct_guess_scores_both_players_and_totals_carry_over() {
+	let address = start_server();
+	let (mut alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	GameMessage::Guess(2, words[0].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	let scores = match next(&mut alice) {
+		GameMessage::ScoreUpdate(scores) => scores,
+		message => panic!("expected a score update, got {:?}", message),
+	};
+
+	// The guess came within a second or two of the round starting.
+	assert_eq!(scores[0], (1, 50));
+	assert_eq!(scores[1].0, 2);
+	assert!((95..=100).contains(&scores[1].1), "guesser scored {}", scores[1].1);
+
+	let mut carol = join(address, "carol");
+	assert!(matches!(next(&mut carol), GameMessage::Roster(3, _)));
+	let mut totals = scores.clone();
+	totals.push((3, 0));
+	assert_eq!(next(&mut carol), GameMessage::ScoreUpdate(totals));
+}
+
+#[test]
+fn match_ends_with_a_summary_after_the_last_round() {
+	let settings = Settings { rounds: 1, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+	let (mut alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	GameMessage::Guess(2, words[0].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut bob), GameMessage::ScoreUpdate(_)));
+
+	let results = match next(&mut bob) {
+		GameMessage::MatchOver(results) => results,
+		message => panic!("expected the match to end, got {:?}", message),
+	};
+	assert_eq!(results.len(), 1);
+	let RoundResult { word, winner, seconds } = &results[0];
+	assert_eq!(word, &words[0]);
+	assert_eq!(winner.as_deref(), Some("bob"));
+	assert!(*seconds <= 5, "round took {}s", seconds);
+
+	// The server hangs up on everyone once the match is over.
+	assert!(parse_game_message(&mut bob).is_err());
+	assert!(matches!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut alice), GameMessage::ScoreUpdate(_)));
+	assert!(matches!(next(&mut alice), GameMessage::MatchOver(_)));
+	assert!(parse_game_message(&mut alice).is_err());
+}
+
+#[test]
+fn hints_reveal_letters_of_the_word_to_guessers() {
+	let settings = Settings { hints: vec![1, 1], ..Settings::default() };
+	let (alice, mut bob, words) = start_round(start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings)));
+	let word = words[0].clone();
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let hint = match next(&mut bob) {
+		GameMessage::SetWordSkeleton(hint) => hint,
+		message => panic!("expected a hint, got {:?}", message),
+	};
+
+	assert_eq!(hint.len(), word.len());
+	let revealed = hint.chars().zip(word.chars()).filter(|(h, w)| h == w && *w != ' ').count();
+	assert_eq!(revealed, 2);
+	assert!(hint.chars().zip(word.chars()).all(|(h, w)| h == w || h == '_'));
+}
+
+#[test]
+fn only_the_drawers_strokes_are_relayed() {
+	let (alice, mut bob, _) = start_round(start_server());
+	GameMessage::PickWord(1).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let (red, brush) = (Color::new(255, 0, 0), Brush::new(3, BrushShape::Square));
+	GameMessage::Draw(5, 5, red, brush).send(&bob).unwrap();
+	GameMessage::DrawLine(1, 2, 3, 4, red, brush).send(&alice).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::DrawLine(1, 2, 3, 4, red, brush));
+}
+
+#[test]
+fn fill_is_only_sent_to_clients_that_negotiated_it() {
+	let address = start_server();
+	let (alice, mut bob, _) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let mut carol = join_with(address, "carol", 0);
+	assert!(matches!(next(&mut carol), GameMessage::Roster(3, _)));
+	assert_eq!(next(&mut carol), GameMessage::SwapRoles(1));
+	assert!(matches!(next(&mut carol), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut bob), GameMessage::Roster(2, _)));
+
+	let blue = Color::new(0, 0, 255);
+	GameMessage::Fill(10, 10, blue).send(&alice).unwrap();
+	GameMessage::Erase(4, 4, Brush::default()).send(&alice).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Fill(10, 10, blue));
+	assert_eq!(next(&mut bob), GameMessage::Erase(4, 4, Brush::default()));
+	assert_eq!(next(&mut carol), GameMessage::Erase(4, 4, Brush::default()));
+}
+
+#[test]
+fn polylines_reach_older_clients_as_separate_lines() {
+	let address = start_server();
+	let (alice, mut bob, _) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let mut carol = join_with(address, "carol", 0);
+	assert!(matches!(next(&mut carol), GameMessage::Roster(3, _)));
+	assert_eq!(next(&mut carol), GameMessage::SwapRoles(1));
+	assert!(matches!(next(&mut carol), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut bob), GameMessage::Roster(2, _)));
+
+	let red = Color::new(255, 0, 0);
+	let polyline = GameMessage::Polyline { color: red, brush: Brush::default(), points: vec![(1, 1), (5, 1), (5, 9)] };
+	polyline.send(&alice).unwrap();
+	assert_eq!(next(&mut bob), polyline);
+	assert_eq!(next(&mut carol), GameMessage::DrawLine(1, 1, 5, 1, red, Brush::default()));
+	assert_eq!(next(&mut carol), GameMessage::DrawLine(5, 1, 5, 9, red, Brush::default()));
+
+	let erase = GameMessage::ErasePolyline { brush: Brush::default(), points: vec![(5, 9), (2, 9)] };
+	erase.send(&alice).unwrap();
+	assert_eq!(next(&mut bob), erase);
+	assert_eq!(next(&mut carol), GameMessage::EraseLine(5, 9, 2, 9, Brush::default()));
+}
+
+#[test]
+fn drawer_rotates_on_next_round() {
+	let (mut alice, mut bob, words) = start_round(start_server());
+	GameMessage::PickWord(2).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	GameMessage::Guess(2, words[2].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut alice), GameMessage::ScoreUpdate(_)));
+	assert!(matches!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut bob), GameMessage::ScoreUpdate(_)));
+
+	GameMessage::NextRound.send(&alice).unwrap();
+	assert_eq!(next(&mut alice), GameMessage::SwapRoles(2));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(2));
+	assert!(matches!(next(&mut bob), GameMessage::WordChoices(_)));
+}
+
+#[test]
+fn leaving_drawer_hands_over_to_the_next_player() {
+	let address = start_server();
+	let (alice, mut bob, _) = start_round(address);
+
+	let mut carol = join(address, "carol");
+	let everyone = roster(&[(1, "alice"), (2, "bob"), (3, "carol")]);
+	assert_eq!(next(&mut bob), GameMessage::Roster(2, everyone.clone()));
+	assert_eq!(next(&mut carol), GameMessage::Roster(3, everyone));
+	assert_eq!(next(&mut carol), GameMessage::SwapRoles(1));
+
+	drop(alice);
+	let remaining = roster(&[(2, "bob"), (3, "carol")]);
+	assert_eq!(next(&mut carol), GameMessage::Leave(1));
+	assert_eq!(next(&mut carol), GameMessage::Roster(3, remaining.clone()));
+	assert_eq!(next(&mut carol), GameMessage::SwapRoles(2));
+
+	assert_eq!(next(&mut bob), GameMessage::Leave(1));
+	assert_eq!(next(&mut bob), GameMessage::Roster(2, remaining));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(2));
+	assert!(matches!(next(&mut bob), GameMessage::WordChoices(_)));
+}
+
+#[test]
+fn spectators_see_the_answer_but_stay_out_of_the_game() {
+	let address = start_server();
+	let (mut alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let mut watcher = spectate(address, "watcher");
+	assert_eq!(next(&mut watcher), GameMessage::Roster(3, roster(&[(1, "alice"), (2, "bob")])));
+	assert_eq!(next(&mut watcher), GameMessage::SwapRoles(1));
+	assert!(matches!(next(&mut watcher), GameMessage::SetWordSkeleton(_)));
+	assert_eq!(next(&mut watcher), GameMessage::Answer(words[0].clone()));
+	assert!(matches!(next(&mut watcher), GameMessage::CanvasSnapshot(..)));
+
+	// The spectator's guesses are ignored, while everyone else's show up in its feed.
+	GameMessage::Guess(3, words[0].clone()).send(&watcher).unwrap();
+	GameMessage::Guess(2, "zzzzzzzzzzzzzzzzzzzz".to_owned()).send(&bob).unwrap();
+	assert_eq!(next(&mut watcher), GameMessage::Guess(2, "zzzzzzzzzzzzzzzzzzzz".to_owned()));
+	assert_eq!(next(&mut bob), GameMessage::Guess(2, "zzzzzzzzzzzzzzzzzzzz".to_owned()));
+	assert_eq!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Wrong));
+
+	GameMessage::Guess(2, words[0].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut watcher), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut watcher), GameMessage::ScoreUpdate(_)));
+
+	// The drawer passes to bob, not to the spectator who joined after him.
+	GameMessage::NextRound.send(&alice).unwrap();
+	assert_eq!(next(&mut watcher), GameMessage::SwapRoles(2));
+
+	// Nobody is told when a spectator leaves.
+	drop(watcher);
+	assert!(matches!(next(&mut alice), GameMessage::Guess(2, _)));
+	assert!(matches!(next(&mut alice), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut alice), GameMessage::ScoreUpdate(_)));
+	assert_eq!(next(&mut alice), GameMessage::SwapRoles(2));
+	assert!(matches!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Correct(_))));
+	assert!(matches!(next(&mut bob), GameMessage::ScoreUpdate(_)));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(2));
+	assert!(matches!(next(&mut bob), GameMessage::WordChoices(_)));
+
+	GameMessage::PickWord(0).send(&bob).unwrap();
+	assert!(matches!(next(&mut alice), GameMessage::SetWordSkeleton(_)));
+}
+
+#[test]
+fn strokes_are_kept_to_the_hosts_canvas_size() {
+	let settings = Settings { canvas_size: 200, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+
+	let mut alice = connect(address, PROTOCOL_VERSION, CAPABILITIES, "alice");
+	assert!(matches!(next(&mut alice), GameMessage::Hello { canvas_size: 200, .. }));
+	assert!(matches!(next(&mut alice), GameMessage::Settings { .. }));
+	assert!(matches!(next(&mut alice), GameMessage::Roster(1, _)));
+
+	let mut bob = join(address, "bob");
+	assert!(matches!(next(&mut bob), GameMessage::Roster(2, _)));
+	assert!(matches!(next(&mut bob), GameMessage::SwapRoles(1)));
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let red = Color::new(255, 0, 0);
+	GameMessage::DrawLine(10, 10, 200, 10, red, Brush::default()).send(&alice).unwrap();
+	GameMessage::Draw(150, 199, red, Brush::default()).send(&alice).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Draw(150, 199, red, Brush::default()));
+}
+
+/// The red pixels of a snapshot, as (x, y).
+fn red_pixels(message: GameMessage) -> Vec<(u32, u32)> {
+	let (width, pixels) = match message {
+		GameMessage::CanvasSnapshot(width, _, pixels) => (width, pixels),
+		message => panic!("expected a canvas snapshot, got {:?}", message),
+	};
+
+	pixels.chunks(4).enumerate()
+		.filter(|(_, pixel)| *pixel == [255, 0, 0, 255])
+		.map(|(i, _)| (i as u32 % width, i as u32 / width))
+		.collect()
+}
+
+#[test]
+fn reconnecting_player_resumes_the_round_in_progress() {
+	let address = start_server();
+	let (mut alice, mut bob, words) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	let (red, pen) = (Color::new(255, 0, 0), Brush::new(1, BrushShape::Square));
+	GameMessage::DrawLine(1, 2, 3, 4, red, pen).send(&alice).unwrap();
+	GameMessage::Undo.send(&alice).unwrap();
+	GameMessage::Draw(10, 10, red, pen).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut bob), GameMessage::DrawLine(..)));
+	assert_eq!(next(&mut bob), GameMessage::Undo);
+	assert!(matches!(next(&mut bob), GameMessage::Draw(..)));
+
+	drop(bob);
+	assert_eq!(next(&mut alice), GameMessage::Leave(2));
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, roster(&[(1, "alice")])));
+
+	let mut bob = join(address, "bob");
+	let both = roster(&[(1, "alice"), (2, "bob")]);
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, both.clone()));
+	assert_eq!(next(&mut bob), GameMessage::Roster(2, both));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(1));
+	let skeleton: String = words[0].chars().map(|c| if c == ' ' { ' ' } else { '_' }).collect();
+	assert_eq!(next(&mut bob), GameMessage::SetWordSkeleton(skeleton));
+	assert_eq!(red_pixels(next(&mut bob)), vec![(10, 10)]);
+
+	GameMessage::Guess(2, words[0].clone()).send(&bob).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::GuessResult(2, Verdict::Correct(_))));
+}
+
+#[test]
+fn players_keep_their_place_for_as_long_as_clients_try_to_reconnect() {
+	let tick = Duration::from_millis(20);
+	let settings = Settings { round_seconds: 1000, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings).with_tick(tick));
+	let (mut alice, bob, _) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+
+	drop(bob);
+	assert_eq!(next(&mut alice), GameMessage::Leave(2));
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, roster(&[(1, "alice")])));
+
+	// A client that only gets through on its last attempt, after every wait.
+	thread::sleep(tick * (RECONNECT_SECONDS + 1));
+	let mut bob = join(address, "bob");
+	let both = roster(&[(1, "alice"), (2, "bob")]);
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, both.clone()));
+	assert_eq!(next(&mut bob), GameMessage::Roster(2, both));
+	assert_eq!(next(&mut bob), GameMessage::SwapRoles(1));
+}
+
+#[test]
+fn guessers_are_sent_the_picture_periodically() {
+	let settings = Settings { round_seconds: 11, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+	let (alice, mut bob, _) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	let (red, pen) = (Color::new(255, 0, 0), Brush::new(1, BrushShape::Square));
+	GameMessage::DrawLine(0, 0, 2, 0, red, pen).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut bob), GameMessage::DrawLine(..)));
+
+	assert_eq!(red_pixels(next(&mut bob)), vec![(0, 0), (1, 0)]);
+}
+
+#[test]
+fn undo_after_a_snapshot_is_followed_by_a_fresh_one() {
+	let settings = Settings { round_seconds: 11, ..Settings::default() };
+	let address = start(Server::bind("127.0.0.1:0").unwrap().with_settings(settings));
+	let (alice, mut bob, _) = start_round(address);
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	let (red, pen) = (Color::new(255, 0, 0), Brush::new(1, BrushShape::Square));
+	GameMessage::Draw(0, 0, red, pen).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+	assert!(matches!(next(&mut bob), GameMessage::Draw(..)));
+	assert_eq!(red_pixels(next(&mut bob)), vec![(0, 0)]);
+
+	// Bob's history now starts at the snapshot, so his own undo could not take this back.
+	GameMessage::Draw(0, 0, Color::new(0, 0, 255), pen).send(&alice).unwrap();
+	GameMessage::Undo.send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::Draw(..)));
+	assert_eq!(next(&mut bob), GameMessage::Undo);
+	assert_eq!(red_pixels(next(&mut bob)), vec![(0, 0)]);
+
+	GameMessage::Undo.send(&alice).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Undo);
+	assert_eq!(red_pixels(next(&mut bob)), Vec::new());
+}
+
+#[test]
+fn oversized_brushes_drop_the_drawer() {
+	let (alice, mut bob, _) = start_round(start_server());
+	GameMessage::PickWord(0).send(&alice).unwrap();
+	assert!(matches!(next(&mut bob), GameMessage::SetWordSkeleton(_)));
+
+	let points = (0..400).map(|i| (i % 100, i % 100)).collect();
+	let mut polyline = GameMessage::Polyline { color: Color::new(255, 0, 0), brush: Brush::default(), points }.encode();
+	polyline[4] = 255;
+	(&alice).write_all(&polyline).unwrap();
+	assert_eq!(next(&mut bob), GameMessage::Leave(1));
+}
+
+#[test]
+fn malformed_message_drops_only_that_client() {
+	let (mut alice, bob, _) = start_round(start_server());
+
+	(&bob).write_all(&[200]).unwrap();
+	assert_eq!(next(&mut alice), GameMessage::Leave(2));
+	assert_eq!(next(&mut alice), GameMessage::Roster(1, roster(&[(1, "alice")])));
+}
+
+fn greet_local(stream: LocalStream, name: &str) -> (LocalReader, impl Transport) {
+	let (mut reader, writer) = stream.split();
+	writer.send(&GameMessage::Hello {
+		protocol_version: PROTOCOL_VERSION,
+		client_name: name.to_owned(),
+		capabilities: CAPABILITIES,
+		canvas_size: 0,
+		spectating: false,
+	}).unwrap();
+
+	assert!(matches!(parse_game_message(&mut reader).unwrap(), GameMessage::Hello { .. }));
+	assert!(matches!(parse_game_message(&mut reader).unwrap(), GameMessage::Settings { .. }));
+	(reader, writer)
+}
+
+#[test]
+fn local_connections_play_a_match_without_sockets() {
+	let mut server = Server::local().with_settings(Settings { rounds: 1, ..Settings::default() });
+	assert!(server.local_addr().is_err());
+	let (alice, bob) = (server.connect_local(), server.connect_local());
+	thread::spawn(move || server.run());
+
+	let (mut alice, alice_writer) = greet_local(alice, "alice");
+	assert!(matches!(parse_game_message(&mut alice).unwrap(), GameMessage::Roster(1, _)));
+	let (mut bob, bob_writer) = greet_local(bob, "bob");
+	assert!(matches!(parse_game_message(&mut bob).unwrap(), GameMessage::Roster(2, _)));
+	assert_eq!(parse_game_message(&mut bob).unwrap(), GameMessage::SwapRoles(1));
+
+	assert!(matches!(parse_game_message(&mut alice).unwrap(), GameMessage::Roster(1, _)));
+	assert_eq!(parse_game_message(&mut alice).unwrap(), GameMessage::SwapRoles(1));
+	let words = match parse_game_message(&mut alice).unwrap() {
+		GameMessage::WordChoices(words) => words,
+		message => panic!("expected word choices, got {:?}", message),
+	};
+
+	alice_writer.send(&GameMessage::PickWord(0)).unwrap();
+	assert!(matches!(parse_game_message(&mut bob).unwrap(), GameMessage::SetWordSkeleton(_)));
+	bob_writer.send(&GameMessage::Guess(2, words[0].clone())).unwrap();
+
+	// Once the match is over the server hangs up, ending the stream.
+	loop {
+		match parse_game_message(&mut bob) {
+			Ok(GameMessage::MatchOver(results)) => assert_eq!(results[0].winner.as_deref(), Some("bob")),
+			Ok(_) => continue,
+			Err(e) => {
+				assert!(matches!(e, ProtocolError::Eof));
+				break;
+			}
+		}
+	}
+}
@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use pictionary::server::words::{Difficulty, WordList};
+
+fn words_folder() -> &'static Path {
+	Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/words"))
+}
+
+#[test]
+fn packs_can_be_selected_by_name_or_category_and_filtered_by_difficulty() {
+	let animals = WordList::load(words_folder(), &["animals".to_owned()], None).unwrap();
+	assert!(animals.words().iter().all(|word| word.category == "animals" && word.difficulty == Difficulty::Medium));
+
+	let food = WordList::load(words_folder(), &["food".to_owned()], None).unwrap();
+	assert!(food.words().iter().any(|word| word.text == "pizza"));
+	assert!(food.words().iter().all(|word| word.category == "food" && word.difficulty == Difficulty::Easy));
+
+	let hard = WordList::load(words_folder(), &[], Some(Difficulty::Hard)).unwrap();
+	assert!(hard.words().iter().any(|word| word.text == "telescope"));
+	assert!(hard.words().iter().all(|word| word.difficulty == Difficulty::Hard));
+
+	assert!(WordList::load(words_folder(), &["no such pack".to_owned()], None).is_err());
+}
+
+#[test]
+fn words_are_not_offered_again_until_the_list_is_used_up() {
+	let mut words = WordList::default();
+	let total = words.words().len();
+
+	let mut offered = HashSet::new();
+	for _ in 0..total / 2 {
+		for word in words.choose(2) {
+			assert!(offered.insert(word), "a word was offered twice");
+		}
+	}
+
+	assert_eq!(offered.len(), total);
+	assert_eq!(words.choose(2).len(), 2);
+}